@@ -1,3 +1,11 @@
+use crate::arguments::config::{
+    ColorDepth, ColorSample, DitherAlgo, Filter, LumaFormula, ThresholdMode,
+};
+use crate::font::Font;
+use crate::luminance::get_luminance;
+use crate::operations::palette;
+use crate::ramps;
+use crate::terminal::{self, Graphics};
 use crate::{Config, Style};
 use getopts::Options;
 const VERSION: &str = "0.0.8"; // program version
@@ -9,7 +17,38 @@ pub fn parse(args: Vec<String>) -> Option<Config> {
     let mut opts = Options::new();
     opts.optflag("h", "help", "Show this help message");
     opts.optflag("b","background","Will apply the colors on the \"background\" of the characters instead of coloring the foreground");
+    opts.optopt(
+        "",
+        "bg-color",
+        "Followed by a color (hex \"ff8800\" or \"255,136,0\"), transparent pixels will be blended with this color instead of being left blank",
+        "COLOR",
+    );
+    opts.optopt(
+        "",
+        "cell-color",
+        "Followed by one of: {{average, median, dominant}}, how a character's color is picked from the pixels it covers, default to \"average\"",
+        "SAMPLE",
+    );
+    opts.optopt(
+        "",
+        "filter",
+        "Followed by a list of filters applied in order after resizing: {{brightness(N), contrast(N), gamma(N), levels(BLACK,WHITE), autolevels, equalize, sharpen(N), blur(N)}}\n\
+                            \t\t\t\t ex: tai --filter \"contrast(1.3),gamma(0.8),sharpen(1),equalize\" image.png",
+        "FILTERS",
+    );
+    opts.optopt(
+        "",
+        "font",
+        "Followed by a BDF or PSF font file, used by \"--measure\" instead of the bundled font",
+        "FONT_FILE",
+    );
     opts.optflag("c", "colored", "Will return true colored(RGB) art");
+    opts.optopt(
+        "",
+        "colors",
+        "Followed by one of: {{truecolor, 256, 16, 8, none}}, the colors the terminal can show, the colors of the art are mapped to the closest ones. default to \"truecolor\"",
+        "COLORS",
+    );
     opts.optflag("d", "dither", "enables image dithering");
     opts.optopt(
         "o",
@@ -17,6 +56,33 @@ pub fn parse(args: Vec<String>) -> Option<Config> {
         "Followed by a character, This will modify the default character used by (-S onechar)",
         "CHARACTER",
     );
+    opts.optflag(
+        "",
+        "edge-dots",
+        "Draw the outlines of (-S edges) with braille dots instead of line characters",
+    );
+    opts.optflag(
+        "",
+        "no-exif",
+        "Will ignore the image's exif orientation tag and show it as stored",
+    );
+    opts.optflagopt(
+        "",
+        "invert",
+        "Will make dark pixels dense and bright pixels empty, for light terminals. \"--invert=auto\" asks the terminal for its background color to decide",
+        "auto",
+    );
+    opts.optopt(
+        "",
+        "luma",
+        "Followed by one of: {{rec709, linear, rec601, cie, average, max}}, the formula for the brightness of the pixels, \"linear\" is rec709 in linear light. default to \"rec709\"",
+        "FORMULA",
+    );
+    opts.optflag(
+        "m",
+        "measure",
+        "Order the --table characters by their ink measured from a bitmap font and space them evenly (all printable ascii if no --table)",
+    );
     opts.optflag(
         "N",
         "no-scale",
@@ -28,15 +94,51 @@ pub fn parse(args: Vec<String>) -> Option<Config> {
         "used with \"-d\" option, controls the scale number for the dithering, default to 16",
         "NUMBER",
     );
+    opts.optopt(
+        "",
+        "dither-algo",
+        "used with \"-d\" option, followed by one of: {{floyd, atkinson, sierra, sierra2, sierra-lite, jjn, stucki, bayer2, bayer4, bayer8, bluenoise}}, default to \"floyd\"",
+        "ALGO",
+    );
     opts.optflag(
         "O",
         "once",
         "Will play the image's animation only once (no looping)",
     );
+    opts.optopt(
+        "",
+        "output",
+        "Followed by one of: {{auto, text, sixel, kitty, iterm}}, \"auto\" picks the best the terminal supports: the real image (kitty, iterm, sixel), colored characters or plain characters. default to \"text\" (the --style)",
+        "OUTPUT",
+    );
+    opts.optopt(
+        "p",
+        "palette",
+        &format!(
+            "Followed by one of: {{{{{}}}}} or a palette file (GIMP .gpl or hex colors), the image is drawn with the colors of the palette only",
+            palette::get_names().join(", ")
+        ),
+        "PALETTE",
+    );
+    opts.optopt(
+        "r",
+        "ramp",
+        &format!(
+            "Followed by one of: {{{{{}}}}}, a built-in table of characters for the ascii, numbers, blocks and shapes styles",
+            ramps::get_names().join(", ")
+        ),
+        "RAMP",
+    );
+    opts.optopt(
+        "",
+        "ramp-file",
+        "Followed by a text file, every character in the file (commas and spaces too) is used as the table, line breaks are ignored",
+        "FILE",
+    );
     opts.optopt(
         "S",
         "style",
-        "Followed by one of: {{ascii, numbers, blocks, onechar, braille}}, default to \"braille\"",
+        "Followed by one of: {{ascii, shapes, numbers, blocks, halfblocks, quadrants, sextants, octants, onechar, braille, edges, sixel, kitty, iterm}}, default to \"braille\"",
         "STYLE",
     );
     opts.optopt("","sleep","Followed by number, controls the sleep delay(milli seconds) between animation frames. default to 100","MILLI_SECONDS");
@@ -49,10 +151,35 @@ pub fn parse(args: Vec<String>) -> Option<Config> {
     opts.optopt(
         "t",
         "table",
-        "Make a custom ascii table,(works only with \"ascii\" Style) seperated by ','\n\
-                            \t\t\t\t ex: tai -S ascii --table \" ,.,:,x,@\" image.png",
+        "Make a custom ascii table,(works with \"ascii\", \"numbers\", \"blocks\" and \"shapes\" Styles) seperated by ','\n\
+                            \t\t\t\t ex: tai -S ascii --table \" ,.,:,x,@\" image.png\n\
+                            \t\t\t\t a character can take a luminance (0-255) or a range with '=',\n\
+                            \t\t\t\t ex: tai -S ascii --table \" =0-60,.=90,:,x,@=255\" image.png",
         "TABLE_OF_CHARACTERS",
     );
+    opts.optopt(
+        "",
+        "threshold",
+        "Followed by a number (0-255), a fixed threshold for the styles that turn pixels on/off (braille, onechar, ..), default to otsu's automatic threshold",
+        "NUMBER",
+    );
+    opts.optopt(
+        "",
+        "threshold-mode",
+        "Followed by one of: {{otsu, mean, niblack, sauvola}}, the local modes calculate a threshold for every pixel, good for uneven lighting. default to \"otsu\"",
+        "MODE",
+    );
+    opts.optopt(
+        "",
+        "window",
+        "used with the local threshold modes, the size of the pixels neighborhood, default to 15",
+        "NUMBER",
+    );
+    opts.optflag(
+        "",
+        "two-color",
+        "Pick the best character with both foreground and background colors for every cell, works with {{ascii, blocks, braille}} styles",
+    );
     opts.optflag("v", "version", "Print tai's Version and exit!");
 
     let matches = match opts.parse(&args[1..]) {
@@ -87,6 +214,32 @@ pub fn parse(args: Vec<String>) -> Option<Config> {
         // 38(default)==applying the color on the foreground.
         config.background = 48;
     }
+    if matches.opt_present("bg-color") {
+        let color = matches.opt_str("bg-color").unwrap();
+        config.bg_color = match parse_color(&color) {
+            Some(rgb) => Some(rgb),
+            None => {
+                eprintln!(
+                    "invalid color \"{}\" for --bg-color, try -h | --help",
+                    color
+                );
+                return None;
+            }
+        };
+    }
+    if matches.opt_present("cell-color") {
+        let sample = matches.opt_str("cell-color").unwrap();
+        config.cell_color = match check_color_sample_arg(&sample) {
+            Some(sample) => sample,
+            None => {
+                eprintln!(
+                    "there is no cell color named \"{}\", the available ones are: average, median, dominant",
+                    sample
+                );
+                return None;
+            }
+        };
+    }
     if matches.opt_present("d") {
         config.dither = true;
     }
@@ -98,6 +251,26 @@ pub fn parse(args: Vec<String>) -> Option<Config> {
         }
         config.dither_scale = dither_scale;
     }
+    if matches.opt_present("dither-algo") {
+        let algo = matches.opt_str("dither-algo").unwrap();
+        config.dither_algo = match check_dither_algo_arg(&algo) {
+            Some(algo) => algo,
+            None => {
+                eprintln!(
+                    "there is no dithering algorithm named \"{}\", the available ones are: floyd, atkinson, sierra, sierra2, sierra-lite, jjn, stucki, bayer2, bayer4, bayer8, bluenoise",
+                    algo
+                );
+                return None;
+            }
+        };
+        if !config.dither {
+            eprintln!("image dithering is not enabled, for now i will enable it for you");
+            config.dither = true;
+        }
+    }
+    if matches.opt_present("no-exif") {
+        config.exif = false;
+    }
     if matches.opt_present("N") {
         config.original_size = true;
     }
@@ -105,6 +278,16 @@ pub fn parse(args: Vec<String>) -> Option<Config> {
         let style = matches.opt_str("S").unwrap();
         config.style = check_style_arg(&style);
     }
+    if matches.opt_present("edge-dots") {
+        match config.style {
+            Style::Edges => {}
+            _ => {
+                eprintln!("this option only works with edges style (-S edges), for now i will switch it for you");
+                config.style = Style::Edges;
+            }
+        }
+        config.edge_dots = true;
+    }
     if matches.opt_present("onechar") {
         let onechar = matches.opt_get::<char>("onechar").unwrap().unwrap();
         match config.style {
@@ -128,22 +311,188 @@ pub fn parse(args: Vec<String>) -> Option<Config> {
         let scale = matches.opt_get::<u32>("scale").unwrap().unwrap();
         config.scale = scale;
     }
+    if matches.opt_present("palette") {
+        match palette::get_palette(&matches.opt_str("palette").unwrap()) {
+            Ok(colors) => config.palette = Some(colors),
+            Err(e) => {
+                eprintln!("couldn't load the palette, {}", e);
+                return None;
+            }
+        }
+        // the cell colors are moved to the closest palette color, the dominant
+        // color of a cell lands on the palette color most of its pixels have.
+        if !matches.opt_present("cell-color") {
+            config.cell_color = ColorSample::Dominant;
+        }
+    }
+    if matches.opt_present("filter") {
+        config.filters = parse_filters(&matches.opt_str("filter").unwrap());
+    }
+    if matches.opt_present("luma") {
+        let formula = matches.opt_str("luma").unwrap();
+        config.luma = match check_luma_arg(&formula) {
+            Some(formula) => formula,
+            None => {
+                eprintln!(
+                    "there is no luma formula named \"{}\", the available ones are: rec709, linear, rec601, cie, average, max",
+                    formula
+                );
+                return None;
+            }
+        };
+    }
+    if matches.opt_present("invert") {
+        config.invert = match matches.opt_str("invert").as_deref() {
+            None => true,
+            Some("auto") => match terminal::get_background_color() {
+                Some([r, g, b]) => get_luminance(config.luma, r, g, b) > 127.0,
+                None => {
+                    eprintln!("couldn't get the background color of the terminal, the image will not be inverted");
+                    false
+                }
+            },
+            Some(arg) => {
+                eprintln!("--invert takes only \"auto\", not \"{}\", for now i will invert the image for you", arg);
+                true
+            }
+        };
+    }
+    if matches.opt_present("ramp") {
+        let name = matches.opt_str("ramp").unwrap();
+        match ramps::get_ramp(&name) {
+            Some(ramp) => config.table = ramp,
+            None => {
+                eprintln!(
+                    "there is no ramp named \"{}\", the available ramps are: {}",
+                    name,
+                    ramps::get_names().join(", ")
+                );
+                return None;
+            }
+        }
+    }
+    if matches.opt_present("ramp-file") {
+        match ramps::read_ramp_file(&matches.opt_str("ramp-file").unwrap()) {
+            Ok(ramp) => config.table = ramp,
+            Err(e) => {
+                eprintln!("couldn't read the ramp file, {}", e);
+                return None;
+            }
+        }
+    }
     if matches.opt_present("table") {
-        let table = matches
-            .opt_str("table")
-            .unwrap()
-            .split(',')
-            .map(|token| token.trim().chars().next().unwrap_or(' '))
-            .collect::<Vec<char>>();
-        config.table = table;
+        config.table = ramps::parse_table(&matches.opt_str("table").unwrap());
+    }
+    if !config.table.is_empty() {
         match config.style {
-            Style::Ascii => {}
+            Style::Ascii | Style::Numbers | Style::Blocks | Style::Shapes => {}
             _ => {
-                eprintln!("--table option works only with ascii style(-S/--style ascii), for now i will enable it for you");
+                eprintln!("custom tables work only with ascii, numbers, blocks and shapes styles, for now i will switch to ascii for you");
                 config.style = Style::Ascii
             }
         }
     }
+    if matches.opt_present("measure") || matches.opt_present("font") {
+        let font = match Font::open(matches.opt_str("font").as_deref()) {
+            Ok(font) => font,
+            Err(e) => {
+                eprintln!("couldn't load the font, {}", e);
+                return None;
+            }
+        };
+        if config.table.is_empty() {
+            config.table = match config.style {
+                Style::Numbers => ramps::get_ramp("numbers").unwrap(),
+                Style::Blocks => ramps::get_ramp("blocks").unwrap(),
+                Style::Shapes => (' '..='~').collect(),
+                Style::Ascii => (' '..='~').collect(),
+                _ => {
+                    eprintln!("--measure and --font work only with ascii, numbers, blocks and shapes styles, for now i will switch to ascii for you");
+                    config.style = Style::Ascii;
+                    (' '..='~').collect()
+                }
+            };
+        }
+        config.table = font.build_table(&config.table);
+    }
+    if matches.opt_present("threshold-mode") {
+        let mode = matches.opt_str("threshold-mode").unwrap();
+        config.threshold = match check_threshold_mode_arg(&mode) {
+            Some(mode) => mode,
+            None => {
+                eprintln!(
+                    "there is no threshold mode named \"{}\", the available ones are: otsu, mean, niblack, sauvola",
+                    mode
+                );
+                return None;
+            }
+        };
+    }
+    if matches.opt_present("threshold") {
+        let threshold = matches.opt_get::<u8>("threshold").unwrap().unwrap();
+        if matches.opt_present("threshold-mode") {
+            eprintln!("--threshold is a fixed threshold, it will override --threshold-mode");
+        }
+        config.threshold = ThresholdMode::Fixed(threshold);
+    }
+    if matches.opt_present("window") {
+        let window = matches.opt_get::<u32>("window").unwrap().unwrap();
+        match config.threshold {
+            ThresholdMode::Mean | ThresholdMode::Niblack | ThresholdMode::Sauvola => {}
+            _ => {
+                eprintln!("--window works only with the local threshold modes, for now i will use \"sauvola\" for you");
+                config.threshold = ThresholdMode::Sauvola;
+            }
+        }
+        config.threshold_window = window;
+    }
+    if matches.opt_present("two-color") {
+        match config.style {
+            Style::Ascii | Style::Blocks | Style::Braille => {}
+            _ => {
+                eprintln!("--two-color works only with ascii, blocks and braille styles, for now i will switch to ascii for you");
+                config.style = Style::Ascii;
+            }
+        }
+        if !config.colored {
+            eprintln!(
+                "--colored is not enabled to use this option, for now i will enable it for you"
+            );
+            config.colored = true;
+        }
+        config.two_color = true;
+    }
+    if matches.opt_present("output") {
+        let output = matches.opt_str("output").unwrap();
+        if !check_output_arg(&output, &mut config) {
+            eprintln!(
+                "there is no output named \"{}\", the available ones are: auto, text, sixel, kitty, iterm",
+                output
+            );
+            return None;
+        }
+    }
+    if matches.opt_present("colors") {
+        let colors = matches.opt_str("colors").unwrap();
+        config.colors = match check_colors_arg(&colors) {
+            Some(depth) => depth,
+            None => {
+                eprintln!(
+                    "there is no color depth named \"{}\", the available ones are: truecolor, 24bit, 256, 16, 8, none",
+                    colors
+                );
+                return None;
+            }
+        };
+        if config.colors == ColorDepth::None {
+            config.colored = false;
+        } else if !config.colored {
+            eprintln!(
+                "--colored is not enabled to use this option, for now i will enable it for you"
+            );
+            config.colored = true;
+        }
+    }
     if !matches.free.is_empty() {
         config.image_file = matches.free[0].to_string();
     };
@@ -156,8 +505,180 @@ fn check_style_arg(arg: &str) -> Style {
         "ascii" => Style::Ascii,
         "blocks" => Style::Blocks,
         "braille" => Style::Braille,
+        "edges" => Style::Edges,
+        "halfblocks" => Style::HalfBlocks,
+        "iterm" => Style::Iterm,
+        "kitty" => Style::Kitty,
         "numbers" => Style::Numbers,
+        "octants" => Style::Octants,
         "onechar" => Style::OneChar,
+        "quadrants" => Style::Quadrants,
+        "sextants" => Style::Sextants,
+        "shapes" => Style::Shapes,
+        "sixel" => Style::Sixel,
         _ => Style::default(),
     }
 }
+
+// false if the output is unknown
+fn check_output_arg(arg: &str, config: &mut Config) -> bool {
+    match arg {
+        "auto" => match terminal::detect_graphics() {
+            Some(Graphics::Kitty) => config.style = Style::Kitty,
+            Some(Graphics::Iterm) => config.style = Style::Iterm,
+            Some(Graphics::Sixel) => config.style = Style::Sixel,
+            None => {
+                config.colors = terminal::detect_colors();
+                config.colored = config.colors != ColorDepth::None;
+            }
+        },
+        "sixel" => config.style = Style::Sixel,
+        "kitty" => config.style = Style::Kitty,
+        "iterm" => config.style = Style::Iterm,
+        "text" => {}
+        _ => return false,
+    }
+    true
+}
+
+fn check_colors_arg(arg: &str) -> Option<ColorDepth> {
+    match arg {
+        "truecolor" | "24bit" => Some(ColorDepth::TrueColor),
+        "256" => Some(ColorDepth::Ansi256),
+        "16" => Some(ColorDepth::Ansi16),
+        "8" => Some(ColorDepth::Ansi8),
+        "none" => Some(ColorDepth::None),
+        _ => None,
+    }
+}
+
+fn check_color_sample_arg(arg: &str) -> Option<ColorSample> {
+    match arg {
+        "average" => Some(ColorSample::Average),
+        "median" => Some(ColorSample::Median),
+        "dominant" => Some(ColorSample::Dominant),
+        _ => None,
+    }
+}
+
+fn check_luma_arg(arg: &str) -> Option<LumaFormula> {
+    match arg {
+        "rec709" => Some(LumaFormula::Rec709),
+        "linear" => Some(LumaFormula::Linear),
+        "rec601" => Some(LumaFormula::Rec601),
+        "cie" => Some(LumaFormula::Cie),
+        "average" => Some(LumaFormula::Average),
+        "max" => Some(LumaFormula::Max),
+        _ => None,
+    }
+}
+
+fn check_dither_algo_arg(arg: &str) -> Option<DitherAlgo> {
+    match arg {
+        "floyd" => Some(DitherAlgo::FloydSteinberg),
+        "atkinson" => Some(DitherAlgo::Atkinson),
+        "sierra" => Some(DitherAlgo::Sierra),
+        "sierra2" => Some(DitherAlgo::SierraTwoRow),
+        "sierra-lite" => Some(DitherAlgo::SierraLite),
+        "jjn" => Some(DitherAlgo::JarvisJudiceNinke),
+        "stucki" => Some(DitherAlgo::Stucki),
+        "bayer2" => Some(DitherAlgo::Bayer(2)),
+        "bayer4" => Some(DitherAlgo::Bayer(4)),
+        "bayer8" => Some(DitherAlgo::Bayer(8)),
+        "bluenoise" => Some(DitherAlgo::BlueNoise),
+        _ => None,
+    }
+}
+
+fn check_threshold_mode_arg(arg: &str) -> Option<ThresholdMode> {
+    match arg {
+        "otsu" => Some(ThresholdMode::Otsu),
+        "mean" => Some(ThresholdMode::Mean),
+        "niblack" => Some(ThresholdMode::Niblack),
+        "sauvola" => Some(ThresholdMode::Sauvola),
+        _ => None,
+    }
+}
+
+// parsing the filters list, the commas inside the parentheses separate the
+// arguments of a filter, unknown filters are skipped.
+fn parse_filters(arg: &str) -> Vec<Filter> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut depth = 0;
+    for ch in arg.chars() {
+        match ch {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                tokens.push(std::mem::take(&mut token));
+                continue;
+            }
+            _ => {}
+        }
+        token.push(ch);
+    }
+    tokens.push(token);
+
+    let mut filters = Vec::new();
+    for token in tokens.iter().map(|token| token.trim()) {
+        if token.is_empty() {
+            continue;
+        }
+        match check_filter_arg(token) {
+            Some(filter) => filters.push(filter),
+            None => eprintln!("unknown filter \"{}\", skipping it, try -h | --help", token),
+        }
+    }
+    filters
+}
+
+fn check_filter_arg(arg: &str) -> Option<Filter> {
+    let (name, args) = match arg.split_once('(') {
+        Some((name, args)) => (name.trim(), args.trim_end_matches(')')),
+        None => (arg, ""),
+    };
+    let values = args
+        .split(',')
+        .filter(|value| !value.trim().is_empty())
+        .map(|value| value.trim().parse::<f32>().ok())
+        .collect::<Option<Vec<f32>>>()?;
+    let filter = match (name, &values[..]) {
+        ("brightness", [value]) => Filter::Brightness(*value),
+        ("contrast", [value]) => Filter::Contrast(*value),
+        ("gamma", [value]) if *value > 0.0 => Filter::Gamma(*value),
+        ("levels", [black, white]) if black < white => {
+            Filter::Levels(black.clamp(0.0, 255.0) as u8, white.clamp(0.0, 255.0) as u8)
+        }
+        ("autolevels", []) => Filter::AutoLevels,
+        ("equalize", []) => Filter::Equalize,
+        ("sharpen", []) => Filter::Sharpen(1.0),
+        ("sharpen", [value]) => Filter::Sharpen(*value),
+        ("blur", []) => Filter::Blur(1.0),
+        ("blur", [value]) if *value > 0.0 => Filter::Blur(*value),
+        _ => return None,
+    };
+    Some(filter)
+}
+
+// parsing a color written as hex ("ff8800", "#ff8800") or as "r,g,b"
+pub fn parse_color(arg: &str) -> Option<[u8; 3]> {
+    let arg = arg.trim().trim_start_matches('#');
+    if arg.contains(',') {
+        let values = arg
+            .split(',')
+            .map(|token| token.trim().parse::<u8>().ok())
+            .collect::<Option<Vec<u8>>>()?;
+        return match values[..] {
+            [r, g, b] => Some([r, g, b]),
+            _ => None,
+        };
+    }
+    if arg.len() != 6 {
+        return None;
+    }
+    let r = u8::from_str_radix(arg.get(0..2)?, 16).ok()?;
+    let g = u8::from_str_radix(arg.get(2..4)?, 16).ok()?;
+    let b = u8::from_str_radix(arg.get(4..6)?, 16).ok()?;
+    Some([r, g, b])
+}
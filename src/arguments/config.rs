@@ -1,50 +1,151 @@
 use crate::arguments::argument_parsing;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub enum Style {
     Ascii,
     Blocks,
+    #[default]
     Braille,
+    Edges,
+    HalfBlocks,
+    Iterm,
+    Kitty,
     Numbers,
+    Octants,
     OneChar,
+    Quadrants,
+    Sextants,
+    Shapes,
+    Sixel,
+}
+
+// how the styles that turn pixels on/off (braille, onechar, ...) pick the threshold
+#[derive(Debug, Default, Clone, Copy)]
+pub enum ThresholdMode {
+    #[default]
+    Otsu,
+    Fixed(u8),
+    Mean,
+    Niblack,
+    Sauvola,
+}
+
+// how the image is dithered, error diffusion kernels or threshold maps, check dither.rs
+#[derive(Debug, Default, Clone, Copy)]
+pub enum DitherAlgo {
+    #[default]
+    FloydSteinberg,
+    Atkinson,
+    Sierra,
+    SierraTwoRow,
+    SierraLite,
+    JarvisJudiceNinke,
+    Stucki,
+    Bayer(u32),
+    BlueNoise,
+}
+
+// the filters applied to the image after resizing it, in the given order
+#[derive(Debug, Clone, Copy)]
+pub enum Filter {
+    Brightness(f32),
+    Contrast(f32),
+    Gamma(f32),
+    Levels(u8, u8),
+    AutoLevels,
+    Equalize,
+    Sharpen(f32),
+    Blur(f32),
+}
+
+// the formula used to get the luminance of a pixel, check luminance.rs
+#[derive(Debug, Default, Clone, Copy)]
+pub enum LumaFormula {
+    #[default]
+    Rec709,
+    Linear,
+    Rec601,
+    Cie,
+    Average,
+    Max,
+}
+
+// the colors a terminal can show
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ColorDepth {
+    #[default]
+    TrueColor,
+    Ansi256,
+    Ansi16,
+    Ansi8,
+    None,
+}
+
+// how a cell that covers many pixels picks its color
+#[derive(Debug, Default, Clone, Copy)]
+pub enum ColorSample {
+    #[default]
+    Average,
+    Median,
+    Dominant,
 }
 
 #[derive(Debug)]
 pub struct Config {
     pub background: u8,
+    pub bg_color: Option<[u8; 3]>,
+    pub cell_color: ColorSample,
     pub colored: bool,
+    pub colors: ColorDepth,
     pub dither: bool,
+    pub dither_algo: DitherAlgo,
     pub dither_scale: u8,
+    pub edge_dots: bool,
+    pub exif: bool,
+    pub filters: Vec<Filter>,
     pub image_file: String,
+    pub invert: bool,
+    pub luma: LumaFormula,
     pub onechar: char,
     pub original_size: bool,
+    pub palette: Option<Vec<[u8; 3]>>,
     pub scale: u32,
     pub sleep: u64,
     pub style: Style,
     pub table: Vec<char>,
+    pub threshold: ThresholdMode,
+    pub threshold_window: u32,
+    pub two_color: bool,
     pub once: bool,
 }
 
-impl Default for Style {
-    fn default() -> Self {
-        Self::Braille
-    }
-}
-
 impl Default for Config {
     fn default() -> Self {
         Self {
             background: 38,
+            bg_color: None,
+            cell_color: ColorSample::default(),
             colored: false,
+            colors: ColorDepth::default(),
             dither: false,
+            dither_algo: DitherAlgo::default(),
             dither_scale: 16,
+            edge_dots: false,
+            exif: true,
+            filters: vec![],
             image_file: String::new(),
+            invert: false,
+            luma: LumaFormula::default(),
             onechar: '█',
             original_size: false,
+            palette: None,
             scale: 2,
             sleep: 100,
             style: Style::default(),
             table: vec![],
+            threshold: ThresholdMode::default(),
+            threshold_window: 15,
+            two_color: false,
             once: false,
         }
     }
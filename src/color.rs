@@ -0,0 +1,55 @@
+use crate::luminance::{linear_to_srgb, srgb_to_linear};
+
+/* Colors:
+
+the distance between two sRGB colors doesn't match how different they
+look (the eye is more sensitive to green than blue, and the bytes are gamma
+encoded), so the colors are compared in a perceptual color space where the
+straight distance (delta E) is close to the difference we see.
+Oklab is used for all the matching, it's simple and more uniform than CIELAB.
+sources: https://bottosson.github.io/posts/oklab/
+         https://en.wikipedia.org/wiki/Color_difference
+*/
+
+// sRGB to Oklab [L, a, b], L is 0.0 - 1.0
+pub fn srgb_to_oklab(rgb: &[u8; 3]) -> [f32; 3] {
+    let [r, g, b] = rgb.map(srgb_to_linear);
+    let l = (0.41222146 * r + 0.53633255 * g + 0.051445995 * b).cbrt();
+    let m = (0.2119035 * r + 0.6806995 * g + 0.10739696 * b).cbrt();
+    let s = (0.08830246 * r + 0.28171885 * g + 0.6299787 * b).cbrt();
+    [
+        0.21045426 * l + 0.7936178 * m - 0.004072047 * s,
+        1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+        0.025904037 * l + 0.78277177 * m - 0.80867577 * s,
+    ]
+}
+
+// Oklab to sRGB, colors out of the sRGB range are clamped
+pub fn oklab_to_srgb(lab: &[f32; 3]) -> [u8; 3] {
+    let l = (lab[0] + 0.39633778 * lab[1] + 0.21580376 * lab[2]).powi(3);
+    let m = (lab[0] - 0.105561346 * lab[1] - 0.06385417 * lab[2]).powi(3);
+    let s = (lab[0] - 0.08948418 * lab[1] - 1.2914855 * lab[2]).powi(3);
+    let linear = [
+        4.0767417 * l - 3.3077116 * m + 0.23096994 * s,
+        -1.268438 * l + 2.6097574 * m - 0.34131938 * s,
+        -0.0041960863 * l - 0.7034186 * m + 1.7076147 * s,
+    ];
+    linear.map(|c| linear_to_srgb(c).round() as u8)
+}
+
+// the squared delta E (without the square root), for finding the closest color
+pub fn get_squared_distance(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)
+}
+
+// the index of the closest color (all in Oklab) to the given color
+pub fn get_closest_index(lab: &[f32; 3], colors: &[[f32; 3]]) -> usize {
+    let mut best = (0, f32::MAX);
+    for (i, color) in colors.iter().enumerate() {
+        let distance = get_squared_distance(lab, color);
+        if distance < best.1 {
+            best = (i, distance);
+        }
+    }
+    best.0
+}
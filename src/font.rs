@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+
+/* Measuring characters from a bitmap font:
+
+the tables are ordered by how much "ink" every character has, instead of
+guessing it, this reads a bitmap font (BDF or PSF) and counts the pixels
+of every glyph, then orders the characters by their coverage and spaces
+them evenly so every step in luminance is the same step in ink.
+
+the bundled font is a subset of the public domain "fixed" 6x13 font
+from xorg: https://gitlab.freedesktop.org/xorg/font/misc-misc
+*/
+
+const BUNDLED_FONT: &str = include_str!("fonts/6x13.bdf");
+
+// the number of characters in a measured table
+const RAMP_LENGTH: usize = 64;
+
+// ink coverage (0.0 - 1.0) of every glyph in the font
+pub struct Font {
+    coverages: HashMap<char, f32>,
+}
+
+impl Font {
+    // opens a BDF or PSF font file, or the bundled font if no path is given
+    pub fn open(path: Option<&str>) -> Result<Self, String> {
+        let coverages = match path {
+            None => parse_bdf(BUNDLED_FONT),
+            Some(path) => {
+                let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+                if bytes.starts_with(b"STARTFONT") {
+                    parse_bdf(&String::from_utf8_lossy(&bytes))
+                } else {
+                    parse_psf(&bytes)
+                }
+            }
+        };
+        match coverages {
+            Some(coverages) if !coverages.is_empty() => Ok(Self { coverages }),
+            _ => Err("the font is not a valid BDF or PSF font".to_string()),
+        }
+    }
+
+    pub fn get_coverage(&self, ch: char) -> Option<f32> {
+        if ch == ' ' {
+            return Some(0.0);
+        }
+        self.coverages.get(&ch).copied()
+    }
+
+    // order the characters by their coverage and build a table where the
+    // characters are evenly spaced, characters missing from the font are dropped.
+    pub fn build_table(&self, chars: &[char]) -> Vec<char> {
+        let mut measured = Vec::new();
+        for ch in chars {
+            if measured.iter().any(|(c, _)| c == ch) {
+                continue;
+            }
+            match self.get_coverage(*ch) {
+                Some(coverage) => measured.push((*ch, coverage)),
+                None => eprintln!("the character {:?} is not in the font, skipping it", ch),
+            }
+        }
+        if measured.is_empty() {
+            return vec![' '];
+        }
+        measured.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        let min = measured[0].1;
+        let max = measured[measured.len() - 1].1;
+        let range = (max - min).max(f32::EPSILON);
+
+        // every slot of the table takes the character closest to its ink level
+        (0..RAMP_LENGTH)
+            .map(|i| {
+                let level = i as f32 / (RAMP_LENGTH - 1) as f32;
+                measured
+                    .iter()
+                    .min_by(|a, b| {
+                        let da = ((a.1 - min) / range - level).abs();
+                        let db = ((b.1 - min) / range - level).abs();
+                        da.partial_cmp(&db).unwrap()
+                    })
+                    .map(|(ch, _)| *ch)
+                    .unwrap()
+            })
+            .collect()
+    }
+}
+
+// source: https://en.wikipedia.org/wiki/Glyph_Bitmap_Distribution_Format
+fn parse_bdf(text: &str) -> Option<HashMap<char, f32>> {
+    let mut out = HashMap::new();
+    let mut cell = (0u32, 0u32);
+    let mut encoding = None;
+    let mut width = 0;
+    let mut ink = None;
+    for line in text.lines() {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("FONTBOUNDINGBOX") => {
+                let w = tokens.next()?.parse().ok()?;
+                let h = tokens.next()?.parse().ok()?;
+                cell = (w, h);
+            }
+            Some("ENCODING") => {
+                encoding = tokens
+                    .next()
+                    .and_then(|e| e.parse::<u32>().ok())
+                    .and_then(std::char::from_u32);
+            }
+            Some("BBX") => width = tokens.next()?.parse::<u32>().ok()?,
+            Some("BITMAP") => ink = Some(0),
+            Some("ENDCHAR") => {
+                if let (Some(ch), Some(count)) = (encoding, ink) {
+                    let area = (cell.0 * cell.1).max(1) as f32;
+                    out.insert(ch, count as f32 / area);
+                }
+                encoding = None;
+                ink = None;
+            }
+            Some(row) => {
+                // a bitmap row in hex, only the first "width" bits are the glyph
+                if let Some(count) = ink.as_mut() {
+                    let bits = u64::from_str_radix(row, 16).ok()?;
+                    let row_bits = row.len() as u32 * 4;
+                    let mask =
+                        !0u64 << (row_bits - width.min(row_bits)) & (!0u64 >> (64 - row_bits));
+                    *count += (bits & mask).count_ones();
+                }
+            }
+            None => {}
+        }
+    }
+    Some(out)
+}
+
+// source: https://www.win.tue.nl/~aeb/linux/kbd/font-formats-1.html
+fn parse_psf(bytes: &[u8]) -> Option<HashMap<char, f32>> {
+    let read_u32 = |i: usize| -> Option<u32> {
+        Some(u32::from_le_bytes([
+            *bytes.get(i)?,
+            *bytes.get(i + 1)?,
+            *bytes.get(i + 2)?,
+            *bytes.get(i + 3)?,
+        ]))
+    };
+    let (header_size, length, char_size, height, width, has_table, is_psf1) =
+        if bytes.starts_with(&[0x36, 0x04]) {
+            let mode = *bytes.get(2)?;
+            let height = *bytes.get(3)? as u32;
+            let length = if mode & 0x01 != 0 { 512 } else { 256 };
+            (4, length, height, height, 8, mode & 0x02 != 0, true)
+        } else if bytes.starts_with(&[0x72, 0xb5, 0x4a, 0x86]) {
+            let header_size = read_u32(8)? as usize;
+            let flags = read_u32(12)?;
+            let length = read_u32(16)?;
+            let char_size = read_u32(20)?;
+            let height = read_u32(24)?;
+            let width = read_u32(28)?;
+            (
+                header_size,
+                length,
+                char_size,
+                height,
+                width,
+                flags & 1 != 0,
+                false,
+            )
+        } else {
+            return None;
+        };
+
+    let row_size = width.div_ceil(8) as usize;
+    let mut coverages = Vec::new();
+    for glyph in 0..length as usize {
+        let start = header_size + glyph * char_size as usize;
+        let bitmap = bytes.get(start..start + char_size as usize)?;
+        let mut count = 0;
+        for row in bitmap.chunks(row_size).take(height as usize) {
+            for (i, byte) in row.iter().enumerate() {
+                // the padding bits after the width are not part of the glyph
+                let bits = (width as usize).saturating_sub(i * 8).min(8);
+                count += (byte & (0xFFu8 << (8 - bits))).count_ones();
+            }
+        }
+        coverages.push(count as f32 / (width * height).max(1) as f32);
+    }
+
+    let mut out = HashMap::new();
+    if !has_table {
+        // without a unicode table the glyph index is the character code
+        for (i, coverage) in coverages.iter().enumerate() {
+            if let Some(ch) = std::char::from_u32(i as u32) {
+                out.insert(ch, *coverage);
+            }
+        }
+        return Some(out);
+    }
+    let mut i = header_size + length as usize * char_size as usize;
+    for coverage in coverages.iter() {
+        // every glyph lists its characters until the terminator,
+        // sequences (after the start marker) are skipped.
+        let mut in_sequence = false;
+        if is_psf1 {
+            while let (Some(a), Some(b)) = (bytes.get(i), bytes.get(i + 1)) {
+                let value = u16::from_le_bytes([*a, *b]);
+                i += 2;
+                match value {
+                    0xFFFF => break,
+                    0xFFFE => in_sequence = true,
+                    _ if !in_sequence => {
+                        if let Some(ch) = std::char::from_u32(value as u32) {
+                            out.insert(ch, *coverage);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        } else {
+            let end = i + bytes.get(i..)?.iter().position(|b| *b == 0xFF)?;
+            let entry = bytes.get(i..end)?;
+            let plain = entry.split(|b| *b == 0xFE).next().unwrap_or(&[]);
+            for ch in String::from_utf8_lossy(plain).chars() {
+                out.insert(ch, *coverage);
+            }
+            i = end + 1;
+        }
+    }
+    Some(out)
+}
@@ -0,0 +1,12 @@
+// the library side of tai, the binary (main.rs) is a thin wrapper around it,
+// so other programs can convert/encode images the same way.
+pub mod arguments;
+pub mod color;
+pub mod font;
+pub mod luminance;
+pub mod operations;
+pub mod ramps;
+pub mod terminal;
+pub mod utils;
+
+pub use arguments::config::{Config, Style};
@@ -0,0 +1,105 @@
+use crate::arguments::config::{Config, LumaFormula};
+use image::{GrayImage, Luma, RgbaImage};
+use std::sync::OnceLock;
+
+/* Luminance:
+
+the formulas:
+- rec709: the Rec.709 coefficients on the encoded channels (the default)
+- linear: the pixels are gamma encoded (sRGB), so mixing the channels directly
+  is not the real brightness, a pure green and a pure blue come out farther
+  apart than they look. this one linearizes the channels, mixes them with the
+  Rec.709 coefficients and encodes the result back, grays keep their values
+- rec601: the old TV luma, Rec.601 coefficients on the encoded channels
+- cie: the CIE L* lightness (perceptually uniform) scaled to 0-255
+- average: the average of the channels
+- max: the brightest channel (HSV value)
+sources: https://en.wikipedia.org/wiki/Relative_luminance
+         https://en.wikipedia.org/wiki/SRGB
+         https://en.wikipedia.org/wiki/CIELAB_color_space
+*/
+
+// the linear value (0.0 - 1.0) of every sRGB byte
+fn get_linear_table() -> &'static [f32; 256] {
+    static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [0.0; 256];
+        for (i, value) in table.iter_mut().enumerate() {
+            let c = i as f32 / 255.0;
+            *value = if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            };
+        }
+        table
+    })
+}
+
+pub fn srgb_to_linear(c: u8) -> f32 {
+    get_linear_table()[c as usize]
+}
+
+// the inverse of srgb_to_linear, returns 0.0 - 255.0
+pub fn linear_to_srgb(c: f32) -> f32 {
+    let c = c.clamp(0.0, 1.0);
+    let c = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    c * 255.0
+}
+
+// the luminance (0.0 - 255.0) of a color with the given formula
+pub fn get_luminance(formula: LumaFormula, r: u8, g: u8, b: u8) -> f32 {
+    let linear =
+        || 0.2126 * srgb_to_linear(r) + 0.7152 * srgb_to_linear(g) + 0.0722 * srgb_to_linear(b);
+    match formula {
+        LumaFormula::Rec709 => 0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32,
+        LumaFormula::Linear => linear_to_srgb(linear()),
+        LumaFormula::Rec601 => 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32,
+        LumaFormula::Cie => {
+            let y = linear();
+            let l = if y > 216.0 / 24389.0 {
+                116.0 * y.cbrt() - 16.0
+            } else {
+                y * 24389.0 / 27.0
+            };
+            l * 2.55
+        }
+        LumaFormula::Average => (r as f32 + g as f32 + b as f32) / 3.0,
+        LumaFormula::Max => r.max(g).max(b) as f32,
+    }
+}
+
+// the gray (0.0 - 255.0) that has the same luminance as the color, so the
+// formula on the gray pixel gives the luminance of the color again. cie is
+// not a gray value (L* of a gray is not the gray), the gray of the same
+// linear luminance is.
+pub fn get_gray(formula: LumaFormula, r: u8, g: u8, b: u8) -> f32 {
+    match formula {
+        LumaFormula::Cie => get_luminance(LumaFormula::Linear, r, g, b),
+        _ => get_luminance(formula, r, g, b),
+    }
+}
+
+// the luminance that decides the glyphs, with the formula the user picked
+// and flipped with --invert so the dark pixels get the dense glyphs on
+// light terminals.
+pub fn get_luminance_by(config: &Config, r: u8, g: u8, b: u8) -> f32 {
+    let lumi = get_luminance(config.luma, r, g, b);
+    if config.invert {
+        255.0 - lumi
+    } else {
+        lumi
+    }
+}
+
+// a gray image of the luminance of every pixel (alpha is dropped)
+pub fn get_luma_image(img: &RgbaImage, config: &Config) -> GrayImage {
+    GrayImage::from_fn(img.width(), img.height(), |x, y| {
+        let [r, g, b, _] = img.get_pixel(x, y).0;
+        Luma([get_luminance_by(config, r, g, b).round() as u8])
+    })
+}
@@ -1,10 +1,18 @@
-mod arguments;
-mod operations;
-mod utils;
-
-use arguments::config::{Config, Style};
-use operations::{ascii::img_to_ascii, braille::img_to_braille, onechar::img_to_onechar};
 use std::env;
+use tai::operations::{
+    ascii::img_to_ascii,
+    braille::img_to_braille,
+    cell_fit::{img_to_fitted, GlyphSet},
+    edges::img_to_edges,
+    halfblocks::img_to_halfblocks,
+    iterm::img_to_iterm,
+    kitty::img_to_kitty,
+    mosaic::{img_to_octants, img_to_quadrants, img_to_sextants},
+    onechar::img_to_onechar,
+    shapes::img_to_shapes,
+    sixel::img_to_sixel,
+};
+use tai::{ramps, Config, Style};
 
 // TODO1: need better naming for functions and variables, it's sucks because
 //       im not a native English speaker.
@@ -20,32 +28,68 @@ fn main() {
 
     // matching the style givin to decide which operation to apply.
     match config.style {
+        Style::Ascii if config.two_color => {
+            img_to_fitted(config, GlyphSet::Ascii);
+        }
+        Style::Blocks if config.two_color => {
+            img_to_fitted(config, GlyphSet::Blocks);
+        }
+        Style::Braille if config.two_color => {
+            img_to_fitted(config, GlyphSet::Braille);
+        }
         Style::OneChar => {
             img_to_onechar(config);
         }
         Style::Braille => {
             img_to_braille(config);
         }
+        Style::Edges => {
+            img_to_edges(config);
+        }
+        Style::HalfBlocks => {
+            img_to_halfblocks(config);
+        }
+        Style::Octants => {
+            img_to_octants(config);
+        }
+        Style::Quadrants => {
+            img_to_quadrants(config);
+        }
+        Style::Sextants => {
+            img_to_sextants(config);
+        }
         Style::Ascii => {
-            let table = if config.table.is_empty() {
-                vec![
-                    ' ', ' ', ' ', ' ', '.', '.', '.', ',', ',', ',', '\'', ';', ':', '<', '>',
-                    'l', 'o', 'b', 'd', 'x', 'k', 'O', '0', 'K', 'X', 'N', 'W', 'M',
-                ]
-            } else {
-                config.table.clone()
-            };
+            let table = get_table(&config, "ascii");
             img_to_ascii(config, &table);
         }
+        Style::Shapes => {
+            img_to_shapes(config);
+        }
+        Style::Sixel => {
+            img_to_sixel(config);
+        }
+        Style::Kitty => {
+            img_to_kitty(config);
+        }
+        Style::Iterm => {
+            img_to_iterm(config);
+        }
         Style::Numbers => {
-            let table = vec![
-                ' ', ' ', ' ', ' ', '0', '1', '7', '6', '9', '4', '2', '3', '8',
-            ];
+            let table = get_table(&config, "numbers");
             img_to_ascii(config, &table);
         }
         Style::Blocks => {
-            let table = vec![' ', ' ', ' ', ' ', '░', '▒', '▓', '█'];
+            let table = get_table(&config, "blocks");
             img_to_ascii(config, &table);
         }
     };
 }
+
+// the custom table (--table, --ramp, ..) if given, or the style's default ramp
+fn get_table(config: &Config, default: &str) -> Vec<char> {
+    if config.table.is_empty() {
+        ramps::get_ramp(default).unwrap()
+    } else {
+        config.table.clone()
+    }
+}
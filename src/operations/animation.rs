@@ -0,0 +1,101 @@
+use crate::arguments::config::Config;
+use crate::operations::dither::dither_image;
+use crate::utils::{open_and_resize_by, resize_exact_by};
+use image::{gif::GifDecoder, AnimationDecoder, DynamicImage, RgbaImage};
+use std::{fs::File, thread::sleep, time::Duration};
+
+/* shared driver for the styles that convert an image buffer into a string:
+
+- open the image (or decode every frame if it's a gif)
+- resize the buffer and dither it if the user wants to
+- pass the buffer to the style's translate function
+- print the output once, or loop on the frames for animations
+*/
+
+pub fn print_image(config: &Config, translate: fn(&RgbaImage, &Config) -> String) {
+    print_image_by(config, (1.0, 1.0), translate);
+}
+
+// the factor (x, y) multiplies the size of the buffer given to translate,
+// check utils::open_and_resize_by
+pub fn print_image_by(
+    config: &Config,
+    factor: (f32, f32),
+    translate: fn(&RgbaImage, &Config) -> String,
+) {
+    if config.image_file.ends_with(".gif") {
+        print_animated_image(config, factor, translate);
+    } else {
+        print_static_image(config, factor, translate);
+    }
+}
+
+fn print_static_image(
+    config: &Config,
+    factor: (f32, f32),
+    translate: fn(&RgbaImage, &Config) -> String,
+) {
+    let mut img = match open_and_resize_by(config, factor) {
+        Some(img) => img,
+        None => return,
+    };
+    if config.dither {
+        dither_image(&mut img, config);
+    }
+    println!("{}", translate(&img, config));
+}
+
+fn loop_the_animation(config: &Config, frames: &[String]) {
+    for frame in frames {
+        print!("{}", frame);
+        sleep(Duration::from_millis(config.sleep))
+    }
+}
+
+fn print_animated_image(
+    config: &Config,
+    factor: (f32, f32),
+    translate: fn(&RgbaImage, &Config) -> String,
+) {
+    let frames = get_animated_frames(config, factor, translate);
+    if config.once {
+        loop_the_animation(config, &frames);
+    } else {
+        loop {
+            loop_the_animation(config, &frames);
+        }
+    }
+}
+
+// decode the gif and translate each frame, will return a vector
+// containing the translated frames
+fn get_animated_frames(
+    config: &Config,
+    factor: (f32, f32),
+    translate: fn(&RgbaImage, &Config) -> String,
+) -> Vec<String> {
+    let mut out_frames = Vec::new();
+    let file_in = match File::open(&config.image_file) {
+        Ok(file) => file,
+        Err(_) => return out_frames,
+    };
+    let decoder = GifDecoder::new(file_in).unwrap();
+    let frames = decoder
+        .into_frames()
+        .collect_frames()
+        .expect("error decoding gif");
+    // pushing this ansi code to clear the screen in the start of the frames
+    out_frames.push("\x1B[1J".to_string());
+
+    for frame in frames {
+        let img = DynamicImage::ImageRgba8(frame.buffer().clone());
+        let mut img = resize_exact_by(img, config, factor);
+        if config.dither {
+            dither_image(&mut img, config);
+        }
+        // this ansi code will seek/save the cursor position to the start of the art
+        // so for each frame will override the old one in stdout
+        out_frames.push(format!("\x1B[r{}", translate(&img, config)));
+    }
+    out_frames
+}
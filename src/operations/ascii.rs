@@ -1,6 +1,8 @@
 use crate::arguments::config::Config;
-use crate::operations::dither::Dither;
-use crate::utils::{colorize, get_luminance, open_and_resize, resize};
+use crate::luminance::get_luminance_by;
+use crate::operations::dither::dither_image;
+use crate::ramps::is_wide;
+use crate::utils::{colorize, get_cell_color, is_transparent, open_and_resize, resize};
 use image::{gif::GifDecoder, AnimationDecoder, DynamicImage, RgbaImage};
 use std::{fs::File, thread::sleep, time::Duration};
 
@@ -8,7 +10,8 @@ use std::{fs::File, thread::sleep, time::Duration};
 
 algorithm for static images work this way:
     - open the image buffer
-    - loop on the image buffer by 2x2 chuncks
+    - loop on the image buffer by 2x2 chuncks (4x2 if the table has wide
+      characters, they take two columns in the terminal)
     - calculate the luminance of the 2x2 chunck and get the average luminance
     - based on the luminance average select a character from the ascii table
     - print the selected character
@@ -32,28 +35,59 @@ pub fn img_to_ascii(config: Config, table: &[char]) {
     }
 }
 
-// this function will loop into a small chunck of pixels (2*2) and return a string containing a character
-fn get_char(img: &RgbaImage, config: &Config, table: &[char], x: u32, y: u32) -> String {
+// this function will loop into a small chunck of pixels (width*2) and return a string containing a character
+fn get_char(
+    img: &RgbaImage,
+    config: &Config,
+    table: &[char],
+    x: u32,
+    y: u32,
+    width: u32,
+) -> String {
     let mut sum = 0.0;
     let mut count = 0.0;
     for iy in y..y + 2 {
-        for ix in x..x + 2 {
-            let [red, green, blue, _] = img.get_pixel(ix, iy).0;
-            let lumi = get_luminance(red, green, blue);
+        for ix in x..x + width {
+            let [red, green, blue, alpha] = img.get_pixel(ix, iy).0;
+            // transparent pixels are not counted in the average
+            if is_transparent([red, green, blue, alpha]) {
+                continue;
+            }
+            let lumi = get_luminance_by(config, red, green, blue);
             sum += lumi;
             count += 1.0;
         }
     }
+    // a narrow character in a chunck of two columns is followed by a space
+    let padding = if width > 2 { " " } else { "" };
+    // a mostly transparent chunck is left blank without colors
+    if count < width as f32 {
+        return format!(" {}", padding);
+    }
     let lumi_avg = sum / count;
     let cha = table[(lumi_avg / 255.0 * ((table.len() - 1) as f32)) as usize];
+    let padding = if is_wide(cha) { "" } else { padding };
     let cha = if config.colored {
-        let [red, green, blue, _] = img.get_pixel(x, y).0;
-        colorize(&[red, green, blue], cha, config.background)
+        let rgb = get_cell_color(img, config, x, y, width, 2);
+        format!(
+            "{}{}",
+            colorize(&rgb, cha, config.background, config.colors),
+            padding
+        )
     } else {
-        format!("{}", cha)
+        format!("{}{}", cha, padding)
     };
     cha
 }
+
+// the width of the chunck of pixels for every character
+fn get_cell_width(table: &[char]) -> u32 {
+    if table.iter().any(|ch| is_wide(*ch)) {
+        4
+    } else {
+        2
+    }
+}
 // process a static image
 fn print_static_image(config: &Config, table: &[char]) {
     let mut img = match open_and_resize(config) {
@@ -62,12 +96,13 @@ fn print_static_image(config: &Config, table: &[char]) {
     };
 
     if config.dither {
-        img.dither(config.dither_scale);
+        dither_image(&mut img, config);
     };
 
-    for y in (0..img.height() - 2).step_by(2) {
-        for x in (0..img.width() - 2).step_by(2) {
-            let ch = get_char(&img, config, table, x, y);
+    let width = get_cell_width(table);
+    for y in (0..img.height().saturating_sub(2)).step_by(2) {
+        for x in (0..img.width().saturating_sub(width)).step_by(width as usize) {
+            let ch = get_char(&img, config, table, x, y, width);
             print!("{}", ch);
         }
         println!();
@@ -117,7 +152,7 @@ fn get_animated_frames(config: &Config, table: &[char]) -> Vec<String> {
         let img = DynamicImage::ImageRgba8(frame.buffer().clone());
         let mut img = resize(img, config);
         if config.dither {
-            img.dither(config.dither_scale);
+            dither_image(&mut img, config);
         }
 
         let translated_frame = translate_frame(&img, config, table);
@@ -132,9 +167,10 @@ fn get_animated_frames(config: &Config, table: &[char]) -> Vec<String> {
 // this function will convert the pixels into ascii chars, put it in a string and return it
 fn translate_frame(img: &RgbaImage, config: &Config, table: &[char]) -> String {
     let mut out = String::new();
-    for y in (0..img.height() - 2).step_by(2) {
-        for x in (0..img.width() - 2).step_by(2) {
-            let cha = get_char(img, config, table, x, y);
+    let width = get_cell_width(table);
+    for y in (0..img.height().saturating_sub(2)).step_by(2) {
+        for x in (0..img.width().saturating_sub(width)).step_by(width as usize) {
+            let cha = get_char(img, config, table, x, y, width);
             out.push_str(&cha);
         }
         out.push('\n');
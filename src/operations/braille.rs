@@ -1,10 +1,11 @@
 use crate::arguments::config::Config;
-use crate::operations::dither::Dither;
+use crate::luminance::get_luminance_by;
+use crate::operations::dither::dither_image;
 use crate::utils::*;
-use image::{gif::GifDecoder, AnimationDecoder, DynamicImage, RgbaImage};
+use image::{gif::GifDecoder, AnimationDecoder, DynamicImage, GrayImage, RgbaImage};
 use std::{fs::File, thread::sleep, time::Duration};
 
-use super::otsu_threshold::OtsuThreshold;
+use super::threshold::get_thresholds;
 
 /* Image to braille:
    source: https://en.wikipedia.org/wiki/Braille_Patterns
@@ -23,38 +24,43 @@ pub fn img_to_braille(config: Config) {
         print_animated_image(&config);
     } else {
         // checking the image file is valid,if so opening the image.
-        let img = if let Ok(image) = image::open(&config.image_file) {
-            image
-        } else {
-            return eprintln!(
-                "Image path is not correct, OR image format is not supported!\n try -h | --help"
-            );
+        let img = match open_image(&config) {
+            Some(img) => img,
+            None => return,
         };
         // resizing the image and converting it to "imagebuffer",
         let mut img = resize(img, &config);
         // checking if the user wants to dither the image.
         if config.dither {
-            img.dither(config.dither_scale);
+            dither_image(&mut img, &config);
         };
 
         print_static(&img, &config);
     }
 }
 
-// taking a threshold value, image buffer, and origin pixel coordinates(x,y);
+// taking a threshold map, image buffer, and origin pixel coordinates(x,y);
 // will calculate the pixels from the origin pixel(the x,y is the pixel coordinates) and
 // return a block of signals for everypixel.
-fn get_block_signals(threshold: u8, img: &RgbaImage, coord_x: u32, coord_y: u32) -> [[u8; 2]; 4] {
+fn get_block_signals(
+    config: &Config,
+    thresholds: &GrayImage,
+    img: &RgbaImage,
+    coord_x: u32,
+    coord_y: u32,
+) -> [[u8; 2]; 4] {
     let mut pixel_map = [[0u8; 2]; 4];
     for iy in 0..4 {
         for ix in 0..2 {
-            let [red, green, blue, _] = img.get_pixel(coord_x + ix, coord_y + iy).0;
-            pixel_map[(iy) as usize][(ix) as usize] =
-                if get_luminance(red, green, blue) > threshold as f32 {
-                    1
-                } else {
-                    continue;
-                };
+            let [red, green, blue, alpha] = img.get_pixel(coord_x + ix, coord_y + iy).0;
+            let threshold = thresholds.get_pixel(coord_x + ix, coord_y + iy).0[0];
+            pixel_map[(iy) as usize][(ix) as usize] = if !is_transparent([red, green, blue, alpha])
+                && get_luminance_by(config, red, green, blue) > threshold as f32
+            {
+                1
+            } else {
+                continue;
+            };
         }
     }
     pixel_map
@@ -62,7 +68,7 @@ fn get_block_signals(threshold: u8, img: &RgbaImage, coord_x: u32, coord_y: u32)
 
 // this is the core parser function it will take a blocks of pixels converted to signals
 // (1 = raised pixel, 0 = unraised pixel), and then convert it to a binary and then to a valid char.
-fn translate(map: &mut [[u8; 2]; 4]) -> char {
+pub fn translate(map: &mut [[u8; 2]; 4]) -> char {
     /* our pixel block(map) look like this:
           ---------
           | 0 | 1 |
@@ -98,19 +104,50 @@ fn translate(map: &mut [[u8; 2]; 4]) -> char {
     std::char::from_u32(c + 0x2800).unwrap()
 }
 
+// the color of the raised dots only, so the unraised pixels
+// don't mix in the color, or the whole block if no dots are raised.
+fn get_dots_color(
+    img: &RgbaImage,
+    config: &Config,
+    map: &[[u8; 2]; 4],
+    coord_x: u32,
+    coord_y: u32,
+) -> [u8; 3] {
+    let mut colors = Vec::new();
+    for (iy, row) in map.iter().enumerate() {
+        for (ix, signal) in row.iter().enumerate() {
+            if *signal == 1 {
+                let [r, g, b, _] = img.get_pixel(coord_x + ix as u32, coord_y + iy as u32).0;
+                colors.push([r, g, b]);
+            }
+        }
+    }
+    reduce_cell_colors(&colors, config)
+        .unwrap_or_else(|| get_cell_color(img, config, coord_x, coord_y, 2, 4))
+}
+
+// checking if all the pixels of the block are transparent
+fn is_transparent_block(img: &RgbaImage, coord_x: u32, coord_y: u32) -> bool {
+    (coord_y..coord_y + 4)
+        .all(|y| (coord_x..coord_x + 2).all(|x| is_transparent(img.get_pixel(x, y).0)))
+}
+
 // process a static image
 fn print_static(img: &RgbaImage, config: &Config) {
-    let best_threshold = DynamicImage::ImageRgba8(img.clone())
-        .into_luma8()
-        .get_otsu_value();
-
-    for y in (0..img.height() - 4).step_by(4) {
-        for x in (0..img.width() - 2).step_by(2) {
-            let mut map = get_block_signals(best_threshold, img, x, y);
+    let thresholds = get_thresholds(img, config);
+
+    for y in (0..img.height().saturating_sub(4)).step_by(4) {
+        for x in (0..img.width().saturating_sub(2)).step_by(2) {
+            // transparent blocks are left blank without colors
+            if is_transparent_block(img, x, y) {
+                print!(" ");
+                continue;
+            }
+            let mut map = get_block_signals(config, &thresholds, img, x, y);
+            let rgb = get_dots_color(img, config, &map, x, y);
             let ch = translate(&mut map);
             if config.colored {
-                let [r, g, b, _] = img.get_pixel(x, y).0;
-                print!("{}", colorize(&[r, g, b], ch, config.background));
+                print!("{}", colorize(&rgb, ch, config.background, config.colors));
             } else {
                 print!("{}", ch);
             }
@@ -157,7 +194,7 @@ fn get_animated_frames(config: &Config) -> Vec<String> {
         let img = DynamicImage::ImageRgba8(frame.buffer().clone());
         let mut img = resize(img, config);
         if config.dither {
-            img.dither(config.dither_scale);
+            dither_image(&mut img, config);
         }
         let translated_frame = translate_frame(&img, config);
         // this ansi code will seek/save the cursor position to the start of the art
@@ -169,18 +206,20 @@ fn get_animated_frames(config: &Config) -> Vec<String> {
 
 fn translate_frame(img: &RgbaImage, config: &Config) -> String {
     let mut out = String::new();
-    let best_threshold = DynamicImage::ImageRgba8(img.clone())
-        .into_luma8()
-        .get_otsu_value();
+    let thresholds = get_thresholds(img, config);
 
-    for y in (0..img.height() - 4).step_by(4) {
-        for x in (0..img.width() - 2).step_by(2) {
-            let mut map = get_block_signals(best_threshold, img, x, y);
+    for y in (0..img.height().saturating_sub(4)).step_by(4) {
+        for x in (0..img.width().saturating_sub(2)).step_by(2) {
+            if is_transparent_block(img, x, y) {
+                out.push(' ');
+                continue;
+            }
+            let mut map = get_block_signals(config, &thresholds, img, x, y);
+            let rgb = get_dots_color(img, config, &map, x, y);
             let ch = translate(&mut map);
 
             if config.colored {
-                let [r, g, b, _] = img.get_pixel(x, y).0;
-                out.push_str(&colorize(&[r, g, b], ch, config.background));
+                out.push_str(&colorize(&rgb, ch, config.background, config.colors));
             } else {
                 out.push(ch);
             }
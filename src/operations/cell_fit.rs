@@ -0,0 +1,249 @@
+use crate::arguments::config::Config;
+use crate::color::{get_closest_index, oklab_to_srgb, srgb_to_oklab};
+use crate::operations::animation::print_image_by;
+use crate::operations::braille::translate;
+use crate::operations::mosaic::QUADRANTS;
+use crate::operations::palette::get_palette_oklab;
+use crate::operations::shapes::{get_glyph_mask, GLYPHS};
+use crate::utils::{colorize, colorize_fg_bg, is_transparent};
+use image::RgbaImage;
+
+/* Two colors cell fitting:
+
+every glyph covers some of the cell's sub pixels (the "ink"), the terminal
+paints the ink with the foreground color and the rest with the background color,
+so for every cell:
+
+- collect the sub pixels of the cell
+- for every glyph in the glyph set, find the foreground and background colors
+  that make the glyph look closest to the sub pixels (least squares)
+- pick the glyph (and its two colors) with the smallest error, unless it's not
+  much better than a solid cell, then print a space with the average background
+the fitting is done in Oklab (scaled by 255 like the rgb channels), so the
+error is how different the cell looks, check color.rs
+with a palette the two colors are moved to the closest palette colors, and
+the glyph is scored with those colors.
+*/
+
+// how much (squared error per sub pixel) a glyph must beat a solid cell by,
+// otherwise flat areas get random glyphs in nearly the same two colors,
+// the Oklab distances are about half of the rgb ones for the same colors.
+const MIN_GAIN: f32 = 3.0 * 4.0 * 4.0;
+
+pub enum GlyphSet {
+    Ascii,
+    Blocks,
+    Braille,
+}
+
+// the glyphs of a set with their coverage of the cell's sub pixels (row-major)
+struct Glyphs {
+    width: u32,
+    height: u32,
+    coverages: Vec<(char, Vec<f32>)>,
+}
+
+pub fn img_to_fitted(config: Config, glyph_set: GlyphSet) {
+    // the factor makes every cell cover the same part of the image as an ascii cell,
+    // so the output has the same columns and rows as the ascii style
+    match glyph_set {
+        GlyphSet::Ascii => print_image_by(&config, (2.0, 4.0), |img, config| {
+            translate_frame(img, config, &get_glyphs(GlyphSet::Ascii))
+        }),
+        GlyphSet::Blocks => print_image_by(&config, (1.0, 2.0), |img, config| {
+            translate_frame(img, config, &get_glyphs(GlyphSet::Blocks))
+        }),
+        GlyphSet::Braille => print_image_by(&config, (1.0, 2.0), |img, config| {
+            translate_frame(img, config, &get_glyphs(GlyphSet::Braille))
+        }),
+    }
+}
+
+fn mask_to_coverage(mask: u32, len: usize) -> Vec<f32> {
+    (0..len).map(|i| ((mask >> i) & 1) as f32).collect()
+}
+
+fn get_glyphs(glyph_set: GlyphSet) -> Glyphs {
+    match glyph_set {
+        // (4*8) bitmaps from the shapes style
+        GlyphSet::Ascii => Glyphs {
+            width: 4,
+            height: 8,
+            coverages: GLYPHS
+                .iter()
+                .map(|(ch, rows)| (*ch, mask_to_coverage(get_glyph_mask(rows), 32)))
+                .collect(),
+        },
+        // (2*4): the quadrants with every row doubled, and the lower 1/4 and 3/4 blocks
+        GlyphSet::Blocks => {
+            let mut coverages = QUADRANTS
+                .iter()
+                .enumerate()
+                .map(|(mask, ch)| {
+                    let (top, bottom) = (mask as u32 & 0b11, mask as u32 >> 2);
+                    let mask = top | top << 2 | bottom << 4 | bottom << 6;
+                    (*ch, mask_to_coverage(mask, 8))
+                })
+                .collect::<Vec<_>>();
+            coverages.push(('▂', mask_to_coverage(0b1100_0000, 8)));
+            coverages.push(('▆', mask_to_coverage(0b1111_1100, 8)));
+            Glyphs {
+                width: 2,
+                height: 4,
+                coverages,
+            }
+        }
+        // (2*4): every braille pattern, the dots are treated as filled sub pixels
+        GlyphSet::Braille => Glyphs {
+            width: 2,
+            height: 4,
+            coverages: (0..256u32)
+                .map(|mask| {
+                    let mut map = [[0u8; 2]; 4];
+                    for (i, signal) in map.iter_mut().flatten().enumerate() {
+                        *signal = ((mask >> i) & 1) as u8;
+                    }
+                    (translate(&mut map), mask_to_coverage(mask, 8))
+                })
+                .collect(),
+        },
+    }
+}
+
+// least squares fit of (coverage * fg + (1 - coverage) * bg) to the sub pixels,
+// returns the foreground, the background and the squared error.
+// source: https://en.wikipedia.org/wiki/Linear_least_squares
+fn fit_colors(coverage: &[f32], sub_pixels: &[Option<[f32; 3]>]) -> ([f32; 3], [f32; 3], f32) {
+    let (mut cc, mut cu, mut uu) = (0.0, 0.0, 0.0);
+    let mut cp = [0.0f32; 3];
+    let mut up = [0.0f32; 3];
+    let mut sum = [0.0f32; 3];
+    let mut count = 0.0;
+    for (c, pixel) in coverage.iter().zip(sub_pixels.iter()) {
+        let pixel = match pixel {
+            Some(pixel) => pixel,
+            None => continue,
+        };
+        let u = 1.0 - c;
+        cc += c * c;
+        cu += c * u;
+        uu += u * u;
+        for i in 0..3 {
+            cp[i] += c * pixel[i];
+            up[i] += u * pixel[i];
+            sum[i] += pixel[i];
+        }
+        count += 1.0;
+    }
+    let det = cc * uu - cu * cu;
+    let (fg, bg) = if det.abs() < f32::EPSILON {
+        // the glyph is empty or full, both colors are the average
+        let mean = [sum[0] / count, sum[1] / count, sum[2] / count];
+        (mean, mean)
+    } else {
+        let mut fg = [0.0; 3];
+        let mut bg = [0.0; 3];
+        for i in 0..3 {
+            fg[i] = (uu * cp[i] - cu * up[i]) / det;
+            bg[i] = (cc * up[i] - cu * cp[i]) / det;
+        }
+        (fg, bg)
+    };
+    (fg, bg, get_error(coverage, sub_pixels, &fg, &bg))
+}
+
+// the squared error of the glyph drawn with the two colors
+fn get_error(
+    coverage: &[f32],
+    sub_pixels: &[Option<[f32; 3]>],
+    fg: &[f32; 3],
+    bg: &[f32; 3],
+) -> f32 {
+    let mut error = 0.0;
+    for (c, pixel) in coverage.iter().zip(sub_pixels.iter()) {
+        if let Some(pixel) = pixel {
+            for i in 0..3 {
+                let predicted = c * fg[i] + (1.0 - c) * bg[i];
+                error += (predicted - pixel[i]).powi(2);
+            }
+        }
+    }
+    error
+}
+
+// the palette colors and the same colors in Oklab (scaled by 255)
+type Palette<'a> = Option<(&'a [[u8; 3]], Vec<[f32; 3]>)>;
+
+fn get_char(
+    img: &RgbaImage,
+    config: &Config,
+    glyphs: &Glyphs,
+    palette: &Palette,
+    x: u32,
+    y: u32,
+) -> String {
+    let mut sub_pixels = Vec::new();
+    for iy in y..y + glyphs.height {
+        for ix in x..x + glyphs.width {
+            let [r, g, b, a] = img.get_pixel(ix, iy).0;
+            sub_pixels.push(if is_transparent([r, g, b, a]) {
+                None
+            } else {
+                Some(srgb_to_oklab(&[r, g, b]).map(|c| c * 255.0))
+            });
+        }
+    }
+    if sub_pixels.iter().all(|p| p.is_none()) {
+        return " ".to_string();
+    }
+    let to_rgb = |c: [f32; 3]| match palette {
+        Some((colors, colors_oklab)) => colors[get_closest_index(&c, colors_oklab)],
+        None => oklab_to_srgb(&c.map(|c| c / 255.0)),
+    };
+    let fit = |coverage: &[f32]| {
+        let (fg, bg, error) = fit_colors(coverage, &sub_pixels);
+        match palette {
+            Some((_, colors_oklab)) => {
+                let snap = |c: [f32; 3]| colors_oklab[get_closest_index(&c, colors_oklab)];
+                let (fg, bg) = (snap(fg), snap(bg));
+                (fg, bg, get_error(coverage, &sub_pixels, &fg, &bg))
+            }
+            None => (fg, bg, error),
+        }
+    };
+    // an empty coverage fits both colors to the average, a solid cell
+    let (_, solid, solid_error) = fit(&vec![0.0; sub_pixels.len()]);
+
+    let mut best = (' ', [0.0; 3], [0.0; 3], f32::MAX);
+    for (ch, coverage) in glyphs.coverages.iter() {
+        let (fg, bg, error) = fit(coverage);
+        if error < best.3 {
+            best = (*ch, fg, bg, error);
+        }
+    }
+    let (ch, fg, bg, error) = best;
+    let count = sub_pixels.iter().flatten().count() as f32;
+    if solid_error - error < MIN_GAIN * count {
+        return colorize(&to_rgb(solid), ' ', 48, config.colors);
+    }
+    colorize_fg_bg(&to_rgb(fg), &to_rgb(bg), ch, config.colors)
+}
+
+fn translate_frame(img: &RgbaImage, config: &Config, glyphs: &Glyphs) -> String {
+    let palette = config.palette.as_deref().map(|colors| {
+        let colors_oklab = get_palette_oklab(colors)
+            .iter()
+            .map(|c| c.map(|c| c * 255.0))
+            .collect();
+        (colors, colors_oklab)
+    });
+    let mut out = String::new();
+    for y in (0..(img.height() + 1).saturating_sub(glyphs.height)).step_by(glyphs.height as usize) {
+        for x in (0..(img.width() + 1).saturating_sub(glyphs.width)).step_by(glyphs.width as usize)
+        {
+            out.push_str(&get_char(img, config, glyphs, &palette, x, y));
+        }
+        out.push('\n');
+    }
+    out
+}
@@ -1,66 +1,264 @@
+use crate::arguments::config::{Config, DitherAlgo};
+use crate::operations::palette::{get_closest_color, get_palette_oklab};
+use crate::utils::is_transparent;
 use image::RgbaImage;
-// This is error diff algorithm check the source below.
-// source : https://en.wikipedia.org/wiki/Floyd-Steinberg_dithering
+use std::sync::OnceLock;
+
+/* ERROR DIFFUSION
+
+every pixel takes the closest color, and the difference (the error) is spread
+to the neighbor pixels that are not processed yet, each one gets a part of the
+error based on the kernel weights.
+the rows are scanned in a serpentine order (left to right, then right to left
+with the kernel mirrored), so the error doesn't drift to one side of the image.
+source : https://en.wikipedia.org/wiki/Floyd-Steinberg_dithering
+https://en.wikipedia.org/wiki/Error_diffusion
+*/
+
+/* ORDERED DITHERING
+
+every pixel is shifted by a value from a threshold map (tiled over the image)
+before taking the closest color, so the pixels between two colors end up as
+a pattern of both colors.
+- bayer: the classic recursive matrices (2x2, 4x4, 8x8), gives a cross hatch pattern
+- blue noise: a map generated with the void and cluster method, gives a grainy
+  pattern without visible structure
+source : https://en.wikipedia.org/wiki/Ordered_dithering
+https://en.wikipedia.org/wiki/Void-and-cluster_method
+*/
+
+// the neighbors (dx, dy) getting a part (weight / divisor) of the error
+pub struct Kernel {
+    divisor: f32,
+    weights: &'static [(i32, i32, f32)],
+}
+
+const FLOYD_STEINBERG: Kernel = Kernel {
+    divisor: 16.0,
+    weights: &[(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)],
+};
+
+// only 6/8 of the error is spread, the highlights and shadows keep more contrast
+const ATKINSON: Kernel = Kernel {
+    divisor: 8.0,
+    weights: &[
+        (1, 0, 1.0),
+        (2, 0, 1.0),
+        (-1, 1, 1.0),
+        (0, 1, 1.0),
+        (1, 1, 1.0),
+        (0, 2, 1.0),
+    ],
+};
+
+const SIERRA: Kernel = Kernel {
+    divisor: 32.0,
+    weights: &[
+        (1, 0, 5.0),
+        (2, 0, 3.0),
+        (-2, 1, 2.0),
+        (-1, 1, 4.0),
+        (0, 1, 5.0),
+        (1, 1, 4.0),
+        (2, 1, 2.0),
+        (-1, 2, 2.0),
+        (0, 2, 3.0),
+        (1, 2, 2.0),
+    ],
+};
+
+const SIERRA_TWO_ROW: Kernel = Kernel {
+    divisor: 16.0,
+    weights: &[
+        (1, 0, 4.0),
+        (2, 0, 3.0),
+        (-2, 1, 1.0),
+        (-1, 1, 2.0),
+        (0, 1, 3.0),
+        (1, 1, 2.0),
+        (2, 1, 1.0),
+    ],
+};
+
+const SIERRA_LITE: Kernel = Kernel {
+    divisor: 4.0,
+    weights: &[(1, 0, 2.0), (-1, 1, 1.0), (0, 1, 1.0)],
+};
+
+const JARVIS_JUDICE_NINKE: Kernel = Kernel {
+    divisor: 48.0,
+    weights: &[
+        (1, 0, 7.0),
+        (2, 0, 5.0),
+        (-2, 1, 3.0),
+        (-1, 1, 5.0),
+        (0, 1, 7.0),
+        (1, 1, 5.0),
+        (2, 1, 3.0),
+        (-2, 2, 1.0),
+        (-1, 2, 3.0),
+        (0, 2, 5.0),
+        (1, 2, 3.0),
+        (2, 2, 1.0),
+    ],
+};
+
+const STUCKI: Kernel = Kernel {
+    divisor: 42.0,
+    weights: &[
+        (1, 0, 8.0),
+        (2, 0, 4.0),
+        (-2, 1, 2.0),
+        (-1, 1, 4.0),
+        (0, 1, 8.0),
+        (1, 1, 4.0),
+        (2, 1, 2.0),
+        (-2, 2, 1.0),
+        (-1, 2, 2.0),
+        (0, 2, 4.0),
+        (1, 2, 2.0),
+        (2, 2, 1.0),
+    ],
+};
+
+// the size of the generated blue noise map and the spread of its gaussian filter
+const BLUE_NOISE_SIZE: usize = 64;
+const BLUE_NOISE_SIGMA: f32 = 1.5;
+
+// a square threshold map, the values are in (0, 1)
+pub struct ThresholdMap {
+    size: usize,
+    values: Vec<f32>,
+}
+
+impl ThresholdMap {
+    // the map is tiled over the image
+    fn get(&self, x: u32, y: u32) -> f32 {
+        let (x, y) = (x as usize % self.size, y as usize % self.size);
+        self.values[y * self.size + x]
+    }
+
+    // turn the ranks (0..size*size) into thresholds in the middle of their steps
+    fn from_ranks(size: usize, ranks: &[usize]) -> Self {
+        let count = (size * size) as f32;
+        Self {
+            size,
+            values: ranks.iter().map(|r| (*r as f32 + 0.5) / count).collect(),
+        }
+    }
+}
 
 pub trait Dither {
-    fn dither(&mut self, scale: u8);
+    fn diffuse(&mut self, kernel: &Kernel, closest: impl Fn([u8; 4]) -> [u8; 4]);
+    fn ordered(&mut self, map: &ThresholdMap, spread: f32, closest: impl Fn([u8; 4]) -> [u8; 4]);
     fn calculate_pixel(&mut self, pixel_coord: (u32, u32), err_pixel: [f32; 3], cal: f32);
 }
 
-impl Dither for RgbaImage {
-    fn dither(&mut self, dither_scale: u8) {
-        let scale = dither_scale as f32;
+// dither the image with the palette if the user gave one, or with the dither scale
+pub fn dither_image(img: &mut RgbaImage, config: &Config) {
+    let scale = config.dither_scale as f32;
+    let palette_oklab = config.palette.as_deref().map(get_palette_oklab);
+    let closest = |pixel: [u8; 4]| match (&config.palette, &palette_oklab) {
+        (Some(palette), Some(palette_oklab)) => {
+            let rgb = [pixel[0], pixel[1], pixel[2]];
+            let [r, g, b] = get_closest_color(&rgb, palette, palette_oklab);
+            [r, g, b, pixel[3]]
+        }
+        _ => find_closest_color(pixel, scale),
+    };
+    // the threshold maps shift the pixels by up to the distance between two colors
+    let spread = match &config.palette {
+        Some(palette) => get_palette_spread(palette),
+        None => 255.0 / scale,
+    };
+    match config.dither_algo {
+        DitherAlgo::FloydSteinberg => img.diffuse(&FLOYD_STEINBERG, closest),
+        DitherAlgo::Atkinson => img.diffuse(&ATKINSON, closest),
+        DitherAlgo::Sierra => img.diffuse(&SIERRA, closest),
+        DitherAlgo::SierraTwoRow => img.diffuse(&SIERRA_TWO_ROW, closest),
+        DitherAlgo::SierraLite => img.diffuse(&SIERRA_LITE, closest),
+        DitherAlgo::JarvisJudiceNinke => img.diffuse(&JARVIS_JUDICE_NINKE, closest),
+        DitherAlgo::Stucki => img.diffuse(&STUCKI, closest),
+        // the scale levels are rounded up, so the shift is made to round them to the nearest
+        DitherAlgo::Bayer(size) => img.ordered(&get_bayer_map(size), spread, |pixel| {
+            closest(shift_for_ceil(pixel, &config.palette, spread))
+        }),
+        DitherAlgo::BlueNoise => img.ordered(get_blue_noise_map(), spread, |pixel| {
+            closest(shift_for_ceil(pixel, &config.palette, spread))
+        }),
+    }
+}
 
-        for y in 0..self.height() - 1 {
-            for x in 1..self.width() - 1 {
+impl Dither for RgbaImage {
+    // change every pixel to the closest color and spread the difference
+    fn diffuse(&mut self, kernel: &Kernel, closest: impl Fn([u8; 4]) -> [u8; 4]) {
+        let (width, height) = self.dimensions();
+        for y in 0..height {
+            // the odd rows are scanned from right to left
+            let reverse = y % 2 == 1;
+            for i in 0..width {
+                let x = if reverse { width - 1 - i } else { i };
                 let old_rgb: [u8; 4] = self.get_pixel(x, y).0;
-                let new_rgb: [u8; 4] = find_closest_color(old_rgb, scale);
+                // transparent pixels are not drawn, so they shouldn't spread errors
+                if is_transparent(old_rgb) {
+                    continue;
+                }
+                let new_rgb: [u8; 4] = closest(old_rgb);
 
                 self.get_pixel_mut(x, y).0[..3].clone_from_slice(&new_rgb[..3]);
 
-                let mut pixel = self.get_pixel_mut(x, y).0;
-                pixel[0] = new_rgb[0];
-                pixel[1] = new_rgb[1];
-                pixel[2] = new_rgb[2];
-
                 let err_r: f32 = old_rgb[0] as f32 - new_rgb[0] as f32;
                 let err_g: f32 = old_rgb[1] as f32 - new_rgb[1] as f32;
                 let err_b: f32 = old_rgb[2] as f32 - new_rgb[2] as f32;
                 let err_pixel = [err_r, err_g, err_b];
 
-                self.calculate_pixel((x + 1, y), err_pixel, 7.0);
-                self.calculate_pixel((x - 1, y + 1), err_pixel, 3.0);
-                self.calculate_pixel((x, y + 1), err_pixel, 5.0);
-                self.calculate_pixel((x + 1, y + 1), err_pixel, 1.0);
+                for (dx, dy, weight) in kernel.weights {
+                    let dx = if reverse { -dx } else { *dx };
+                    let (nx, ny) = (x as i64 + dx as i64, y as i64 + *dy as i64);
+                    // the pixels on the edges spread their error only inside the image
+                    if nx < 0 || nx >= width as i64 || ny >= height as i64 {
+                        continue;
+                    }
+                    self.calculate_pixel(
+                        (nx as u32, ny as u32),
+                        err_pixel,
+                        weight / kernel.divisor,
+                    );
+                }
+            }
+        }
+    }
+
+    // shift every pixel by its threshold and change it to the closest color
+    fn ordered(&mut self, map: &ThresholdMap, spread: f32, closest: impl Fn([u8; 4]) -> [u8; 4]) {
+        for (x, y, pixel) in self.enumerate_pixels_mut() {
+            if is_transparent(pixel.0) {
+                continue;
             }
+            let offset = (map.get(x, y) - 0.5) * spread;
+            let [r, g, b, a] = pixel.0;
+            let shifted = [r, g, b].map(|c| (c as f32 + offset).round().clamp(0.0, 255.0) as u8);
+            let new_rgb = closest([shifted[0], shifted[1], shifted[2], a]);
+            pixel.0[..3].clone_from_slice(&new_rgb[..3]);
         }
     }
 
-    // this helper function will calculate the the neighbor pixel and add value from the error pixel as refrenced in wikipedia.
+    // this helper function will calculate the the neighbor pixel and add a part (val) of the error pixel.
     fn calculate_pixel(
         &mut self,
         origin_pixel: (u32, u32), // coordinate (x,y)
         err_pixel: [f32; 3],      // error pixel [R, G, B]
-        val: f32,                 // value will be added to the calculation
+        val: f32,                 // the part of the error will be added to the calculation
     ) {
         // R
-        self.get_pixel_mut(origin_pixel.0, origin_pixel.1).0[0] = (self
-            .get_pixel(origin_pixel.0, origin_pixel.1)
-            .0[0] as f32
-            + err_pixel[0] * val / 16.0)
-            as u8;
+        self.get_pixel_mut(origin_pixel.0, origin_pixel.1).0[0] =
+            (self.get_pixel(origin_pixel.0, origin_pixel.1).0[0] as f32 + err_pixel[0] * val) as u8;
         // G
-        self.get_pixel_mut(origin_pixel.0, origin_pixel.1).0[1] = (self
-            .get_pixel(origin_pixel.0, origin_pixel.1)
-            .0[1] as f32
-            + err_pixel[1] * val / 16.0)
-            as u8;
+        self.get_pixel_mut(origin_pixel.0, origin_pixel.1).0[1] =
+            (self.get_pixel(origin_pixel.0, origin_pixel.1).0[1] as f32 + err_pixel[1] * val) as u8;
         // B
-        self.get_pixel_mut(origin_pixel.0, origin_pixel.1).0[2] = (self
-            .get_pixel(origin_pixel.0, origin_pixel.1)
-            .0[2] as f32
-            + err_pixel[2] * val / 16.0)
-            as u8;
+        self.get_pixel_mut(origin_pixel.0, origin_pixel.1).0[2] =
+            (self.get_pixel(origin_pixel.0, origin_pixel.1).0[2] as f32 + err_pixel[2] * val) as u8;
     }
 }
 
@@ -73,3 +271,162 @@ fn find_closest_color(pixel: [u8; 4], factor: f32) -> [u8; 4] {
         pixel[3],
     ]
 }
+
+// find_closest_color rounds up to the next level, moving the pixels half a level
+// down makes it round to the nearest one (the palettes already pick the nearest)
+fn shift_for_ceil(pixel: [u8; 4], palette: &Option<Vec<[u8; 3]>>, spread: f32) -> [u8; 4] {
+    if palette.is_some() {
+        return pixel;
+    }
+    let [r, g, b, a] = pixel;
+    let [r, g, b] = [r, g, b].map(|c| (c as f32 - spread / 2.0).round().max(0.0) as u8);
+    [r, g, b, a]
+}
+
+// the average distance (per channel) between every palette color and its closest one
+fn get_palette_spread(palette: &[[u8; 3]]) -> f32 {
+    if palette.len() < 2 {
+        return 0.0;
+    }
+    let distance = |a: &[u8; 3], b: &[u8; 3]| {
+        let sum: f32 = (0..3).map(|i| (a[i] as f32 - b[i] as f32).powi(2)).sum();
+        (sum / 3.0).sqrt()
+    };
+    let sum: f32 = palette
+        .iter()
+        .enumerate()
+        .map(|(i, color)| {
+            palette
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, other)| distance(color, other))
+                .fold(f32::MAX, f32::min)
+        })
+        .sum();
+    sum / palette.len() as f32
+}
+
+// the bayer matrix of a size (power of 2) is made from the matrix of the half size:
+// | 4M     4M + 2 |
+// | 4M + 3 4M + 1 |
+fn get_bayer_map(size: u32) -> ThresholdMap {
+    let mut ranks = vec![0usize];
+    let mut current = 1;
+    while current < size as usize {
+        let next = current * 2;
+        let mut next_ranks = vec![0; next * next];
+        for y in 0..next {
+            for x in 0..next {
+                let quadrant = [0, 2, 3, 1][(y / current) * 2 + x / current];
+                next_ranks[y * next + x] =
+                    4 * ranks[(y % current) * current + x % current] + quadrant;
+            }
+        }
+        ranks = next_ranks;
+        current = next;
+    }
+    ThresholdMap::from_ranks(current, &ranks)
+}
+
+// the blue noise map takes a moment to generate, so it's made only once
+fn get_blue_noise_map() -> &'static ThresholdMap {
+    static MAP: OnceLock<ThresholdMap> = OnceLock::new();
+    MAP.get_or_init(|| generate_blue_noise(BLUE_NOISE_SIZE, BLUE_NOISE_SIGMA))
+}
+
+/* Void and cluster:
+
+the "energy" of a pixel is the sum of gaussians centered at the set pixels
+(wrapping around the edges, so the map can be tiled), a high energy means
+the pixel is in a cluster and a low energy means it's in a void.
+
+- start with ~10% random pixels set
+- move the pixel in the tightest cluster to the largest void until they are
+  the same pixel, the set pixels are now evenly spread
+- rank the set pixels by removing the tightest cluster one by one (highest rank first)
+- rank the rest by filling the largest void one by one
+*/
+fn generate_blue_noise(size: usize, sigma: f32) -> ThresholdMap {
+    let count = size * size;
+    // the gaussian of every offset (dx, dy), the offsets wrap around the edges
+    let mut gaussian = vec![0.0f32; count];
+    for dy in 0..size {
+        for dx in 0..size {
+            let (wx, wy) = (dx.min(size - dx) as f32, dy.min(size - dy) as f32);
+            gaussian[dy * size + dx] = (-(wx * wx + wy * wy) / (2.0 * sigma * sigma)).exp();
+        }
+    }
+    let update = |energy: &mut [f32], index: usize, sign: f32| {
+        let (px, py) = (index % size, index / size);
+        for (i, e) in energy.iter_mut().enumerate() {
+            let dx = (i % size + size - px) % size;
+            let dy = (i / size + size - py) % size;
+            *e += sign * gaussian[dy * size + dx];
+        }
+    };
+    let tightest_cluster = |pattern: &[bool], energy: &[f32]| {
+        (0..count).filter(|i| pattern[*i]).fold(0, |best, i| {
+            if !pattern[best] || energy[i] > energy[best] {
+                i
+            } else {
+                best
+            }
+        })
+    };
+    let largest_void = |pattern: &[bool], energy: &[f32]| {
+        (0..count).filter(|i| !pattern[*i]).fold(0, |best, i| {
+            if pattern[best] || energy[i] < energy[best] {
+                i
+            } else {
+                best
+            }
+        })
+    };
+
+    // a fixed seed (xorshift), the map is the same every time
+    let mut pattern = vec![false; count];
+    let mut energy = vec![0.0f32; count];
+    let mut seed: u32 = 0x9E37_79B9;
+    let mut ones = 0;
+    while ones < count / 10 {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        let index = seed as usize % count;
+        if !pattern[index] {
+            pattern[index] = true;
+            update(&mut energy, index, 1.0);
+            ones += 1;
+        }
+    }
+    loop {
+        let cluster = tightest_cluster(&pattern, &energy);
+        pattern[cluster] = false;
+        update(&mut energy, cluster, -1.0);
+        let void = largest_void(&pattern, &energy);
+        pattern[void] = true;
+        update(&mut energy, void, 1.0);
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut ranks = vec![0; count];
+    let (mut removing, mut removing_energy) = (pattern.clone(), energy.clone());
+    for rank in (0..ones).rev() {
+        let cluster = tightest_cluster(&removing, &removing_energy);
+        removing[cluster] = false;
+        update(&mut removing_energy, cluster, -1.0);
+        ranks[cluster] = rank;
+    }
+    // the largest void of the set pixels is the tightest cluster of the unset ones,
+    // so filling the voids works past the half of the map too
+    for rank in ones..count {
+        let void = largest_void(&pattern, &energy);
+        pattern[void] = true;
+        update(&mut energy, void, 1.0);
+        ranks[void] = rank;
+    }
+    ThresholdMap::from_ranks(size, &ranks)
+}
@@ -0,0 +1,127 @@
+use crate::arguments::config::Config;
+use crate::luminance::get_luminance;
+use crate::operations::animation::{print_image, print_image_by};
+use crate::operations::braille::translate;
+use crate::operations::sobel::Sobel;
+use crate::operations::threshold::Threshold;
+use crate::utils::{colorize, get_cell_color, is_transparent};
+use image::{GrayImage, RgbaImage};
+
+/* Image to edges (outlines):
+
+- convert the image buffer to a luma buffer, transparent pixels count as black
+  so the outlines of logos are detected too
+- run the sobel operator on the luma buffer to get the edges and their directions
+- pick the edges threshold with otsu's method on the edges magnitudes
+- for every edge pixel print a line character based on the edge direction,
+  or with --edge-dots group the edge pixels in (2*4) chuncks and print braille
+*/
+
+pub fn img_to_edges(config: Config) {
+    // the braille dots group (2*4) pixels, the buffer is made twice as tall
+    // so the dots keep the same shape as the lines
+    if config.edge_dots {
+        print_image_by(&config, (1.0, 2.0), translate_frame);
+    } else {
+        print_image(&config, translate_frame);
+    }
+}
+
+fn get_luma_buffer(img: &RgbaImage, config: &Config) -> GrayImage {
+    GrayImage::from_fn(img.width(), img.height(), |x, y| {
+        let [r, g, b, a] = img.get_pixel(x, y).0;
+        let lumi = get_luminance(config.luma, r, g, b) * a as f32 / 255.0;
+        image::Luma([lumi as u8])
+    })
+}
+
+// the edge runs perpendicular to the gradient,
+// the y axis is pointing down so a 45 degrees gradient is a '/' edge.
+fn get_line_char(direction: f32) -> char {
+    match direction {
+        d if !(22.5..157.5).contains(&d) => '|',
+        d if d < 67.5 => '/',
+        d if d < 112.5 => '-',
+        _ => '\\',
+    }
+}
+
+// otsu's threshold of the edge pixels only, the rest of the image is zeros
+// which would pull the threshold down and keep the noise.
+fn get_edges_threshold(edges: &GrayImage) -> u8 {
+    let values = edges
+        .iter()
+        .copied()
+        .filter(|p| *p > 0)
+        .collect::<Vec<u8>>();
+    match GrayImage::from_raw(values.len() as u32, 1, values) {
+        Some(img) if img.width() > 0 => img.get_otsu_value(None).max(1),
+        _ => 1,
+    }
+}
+
+fn push_char(out: &mut String, img: &RgbaImage, config: &Config, ch: char, cell: (u32, u32)) {
+    if config.colored && ch != ' ' {
+        let rgb = get_cell_color(img, config, cell.0, cell.1, 1, 1);
+        out.push_str(&colorize(&rgb, ch, config.background, config.colors));
+    } else {
+        out.push(ch);
+    }
+}
+
+fn translate_lines(
+    img: &RgbaImage,
+    config: &Config,
+    edges: &GrayImage,
+    directions: &[f32],
+) -> String {
+    let threshold = get_edges_threshold(edges);
+    let mut out = String::new();
+    for y in 0..img.height() {
+        for x in 0..img.width() {
+            let is_edge = edges.get_pixel(x, y).0[0] >= threshold;
+            let ch = if is_edge && !is_transparent(img.get_pixel(x, y).0) {
+                get_line_char(directions[(y * img.width() + x) as usize])
+            } else {
+                ' '
+            };
+            push_char(&mut out, img, config, ch, (x, y));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn translate_dots(img: &RgbaImage, config: &Config, edges: &GrayImage) -> String {
+    let threshold = get_edges_threshold(edges);
+    let mut out = String::new();
+    for y in (0..(img.height() + 1).saturating_sub(4)).step_by(4) {
+        for x in (0..img.width().saturating_sub(1)).step_by(2) {
+            let mut map = [[0u8; 2]; 4];
+            for (iy, row) in map.iter_mut().enumerate() {
+                for (ix, signal) in row.iter_mut().enumerate() {
+                    let (px, py) = (x + ix as u32, y + iy as u32);
+                    if edges.get_pixel(px, py).0[0] >= threshold {
+                        *signal = 1;
+                    }
+                }
+            }
+            let ch = match translate(&mut map) {
+                '\u{2800}' => ' ',
+                ch => ch,
+            };
+            push_char(&mut out, img, config, ch, (x, y));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn translate_frame(img: &RgbaImage, config: &Config) -> String {
+    let (edges, directions) = get_luma_buffer(img, config).get_edges();
+    if config.edge_dots {
+        translate_dots(img, config, &edges)
+    } else {
+        translate_lines(img, config, &edges, &directions)
+    }
+}
@@ -0,0 +1,164 @@
+use crate::arguments::config::{Config, Filter, LumaFormula};
+use crate::luminance::get_luminance;
+use crate::utils::is_transparent;
+use image::{imageops, RgbaImage};
+
+/* Filters:
+
+applied on the resized image before the styles, so they run on the
+pixels that will become characters (faster than the full image).
+- the "per pixel" filters (brightness, contrast, gamma, levels) build a
+  lookup table of 256 values and map every channel with it
+- autolevels and equalize build the table from the histogram of the
+  luminance of the opaque pixels
+- sharpen and blur work on the neighbour pixels
+the alpha channel is kept as it is.
+*/
+
+// the part of the pixels (0.5%) ignored on each side by autolevels, so a few
+// black or white pixels don't stop the stretching
+const AUTO_LEVELS_CLIP: f32 = 0.005;
+
+pub fn apply_filters(img: &mut RgbaImage, config: &Config) {
+    for filter in config.filters.iter() {
+        match *filter {
+            Filter::Brightness(value) => apply_lut(img, |c| c + value),
+            Filter::Contrast(value) => apply_lut(img, |c| (c - 128.0) * value + 128.0),
+            Filter::Gamma(value) => apply_lut(img, |c| 255.0 * (c / 255.0).powf(1.0 / value)),
+            Filter::Levels(black, white) => stretch(img, black, white),
+            Filter::AutoLevels => {
+                let (black, white) = get_auto_levels(img, config.luma);
+                stretch(img, black, white);
+            }
+            Filter::Equalize => equalize(img, config.luma),
+            Filter::Sharpen(amount) => sharpen(img, amount),
+            Filter::Blur(sigma) => blur(img, sigma),
+        }
+    }
+}
+
+// map every color channel with a function of its value
+fn apply_lut(img: &mut RgbaImage, function: impl Fn(f32) -> f32) {
+    let mut lut = [0u8; 256];
+    for (i, value) in lut.iter_mut().enumerate() {
+        *value = function(i as f32).round().clamp(0.0, 255.0) as u8;
+    }
+    for pixel in img.pixels_mut() {
+        for channel in pixel.0[..3].iter_mut() {
+            *channel = lut[*channel as usize];
+        }
+    }
+}
+
+// stretch the range black-white to the full range 0-255
+fn stretch(img: &mut RgbaImage, black: u8, white: u8) {
+    if black >= white {
+        return;
+    }
+    let range = (white - black) as f32;
+    apply_lut(img, |c| (c - black as f32) * 255.0 / range);
+}
+
+fn get_histogram(img: &RgbaImage, formula: LumaFormula) -> [u32; 256] {
+    let mut histogram = [0u32; 256];
+    for pixel in img.pixels() {
+        if !is_transparent(pixel.0) {
+            let [r, g, b, _] = pixel.0;
+            histogram[get_luminance(formula, r, g, b).round() as usize] += 1;
+        }
+    }
+    histogram
+}
+
+fn get_auto_levels(img: &RgbaImage, formula: LumaFormula) -> (u8, u8) {
+    let histogram = get_histogram(img, formula);
+    let total = histogram.iter().sum::<u32>() as f32;
+    let clip = (total * AUTO_LEVELS_CLIP) as u32;
+    let mut sum = 0;
+    let black = histogram
+        .iter()
+        .position(|count| {
+            sum += count;
+            sum > clip
+        })
+        .unwrap_or(0);
+    sum = 0;
+    let white = 255
+        - histogram
+            .iter()
+            .rev()
+            .position(|count| {
+                sum += count;
+                sum > clip
+            })
+            .unwrap_or(0);
+    (black as u8, white as u8)
+}
+
+// histogram equalization: map every luminance to its place in the
+// cumulative histogram, so all the luminance levels are used equally.
+// the channels are scaled by (new luminance / old luminance), so the
+// colors keep their hue.
+// source: https://en.wikipedia.org/wiki/Histogram_equalization
+fn equalize(img: &mut RgbaImage, formula: LumaFormula) {
+    let histogram = get_histogram(img, formula);
+    let total = histogram.iter().sum::<u32>();
+    let first = histogram
+        .iter()
+        .copied()
+        .find(|count| *count > 0)
+        .unwrap_or(0);
+    if total == first {
+        return;
+    }
+    let mut cdf = [0u32; 256];
+    let mut sum = 0;
+    for (value, count) in cdf.iter_mut().zip(histogram.iter()) {
+        sum += count;
+        *value = sum;
+    }
+    let mut lut = [0.0f32; 256];
+    for (value, count) in lut.iter_mut().zip(cdf.iter()) {
+        *value = count.saturating_sub(first) as f32 * 255.0 / (total - first) as f32;
+    }
+    for pixel in img.pixels_mut() {
+        if is_transparent(pixel.0) {
+            continue;
+        }
+        let [r, g, b, _] = pixel.0;
+        let old = get_luminance(formula, r, g, b);
+        let new = lut[old.round() as usize];
+        for channel in pixel.0[..3].iter_mut() {
+            // black has no hue to keep, it becomes a gray of the new luminance
+            *channel = if old < 0.5 {
+                new
+            } else {
+                *channel as f32 * new / old
+            }
+            .round()
+            .clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+// unsharp masking: adding the difference between the image and a blurred
+// copy of it, the amount controls how strong the edges get.
+// source: https://en.wikipedia.org/wiki/Unsharp_masking
+// blur the colors, the alpha is kept so the edges of transparent images
+// don't become half transparent
+fn blur(img: &mut RgbaImage, sigma: f32) {
+    let blurred = imageops::blur(img, sigma);
+    for (pixel, blurred) in img.pixels_mut().zip(blurred.pixels()) {
+        pixel.0[..3].clone_from_slice(&blurred.0[..3]);
+    }
+}
+
+fn sharpen(img: &mut RgbaImage, amount: f32) {
+    let blurred = imageops::blur(img, 1.0);
+    for (pixel, blurred) in img.pixels_mut().zip(blurred.pixels()) {
+        for (channel, blurred) in pixel.0[..3].iter_mut().zip(blurred.0.iter()) {
+            let value = *channel as f32 + amount * (*channel as f32 - *blurred as f32);
+            *channel = value.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use crate::arguments::config::Config;
+use image::{codecs::png::PngEncoder, ColorType, RgbaImage};
+
+/* shared helpers for the styles that send the real image to the terminal
+(sixel, kitty, iterm2), instead of turning it into characters:
+
+- the image is resized 4 times wider and 8 times taller than the buffer of
+  the character styles, that's (8*16) pixels for every character cell, close
+  to the size of a real terminal cell, so the image covers the same cells
+  as the ascii art of the same scale.
+- with --no-scale the original image is sent as it is.
+- inside tmux or screen the escape sequences are wrapped so the multiplexer
+  passes them to the terminal instead of swallowing them (tmux needs
+  "set -g allow-passthrough on" since tmux 3.3).
+*/
+
+// the pixels of the image for every terminal cell
+pub const CELL_PIXELS: (u32, u32) = (8, 16);
+
+// the factor to pass to utils::open_and_resize_by/resize_by
+pub fn get_factor(config: &Config) -> (f32, f32) {
+    if config.original_size {
+        (1.0, 1.0)
+    } else {
+        (CELL_PIXELS.0 as f32 / 2.0, CELL_PIXELS.1 as f32 / 2.0)
+    }
+}
+
+// the columns and rows of terminal cells the image is placed in
+pub fn get_cell_size(img: &RgbaImage) -> (u32, u32) {
+    (
+        (img.width() / CELL_PIXELS.0).max(1),
+        (img.height() / CELL_PIXELS.1).max(1),
+    )
+}
+
+// same as get_cell_size, for an image of (width * height) that is not resized
+pub fn get_cell_size_of(config: &Config, width: u32, height: u32) -> (u32, u32) {
+    let (width, height) = match config.original_size {
+        true => (width, height),
+        false => (width / config.scale / 2, height / config.scale / 4),
+    };
+    let factor = get_factor(config);
+    (
+        ((width as f32 * factor.0) as u32 / CELL_PIXELS.0).max(1),
+        ((height as f32 * factor.1) as u32 / CELL_PIXELS.1).max(1),
+    )
+}
+
+// wrap the escape sequences in the passthrough of tmux or screen
+// if tai is running inside one of them, check wrap_tmux and wrap_screen.
+pub fn passthrough(sequences: &str) -> String {
+    if std::env::var_os("TMUX").is_some() {
+        wrap_tmux(sequences)
+    } else if std::env::var_os("STY").is_some() {
+        wrap_screen(sequences)
+    } else {
+        sequences.to_string()
+    }
+}
+
+// source: https://github.com/tmux/tmux/wiki/FAQ#what-is-the-passthrough-escape-sequence-and-how-do-i-use-it
+// tmux passes everything in "ESC P tmux; ... ESC \" with every ESC doubled
+pub fn wrap_tmux(sequences: &str) -> String {
+    format!("\x1BPtmux;{}\x1B\\", sequences.replace('\x1B', "\x1B\x1B"))
+}
+
+// screen passes the content of every "ESC P ... ESC \" but it can't hold more than
+// 768 bytes, and an ESC inside would end it, so the sequences are split in
+// pieces of at most 768 bytes that end with an ESC (like libsixel does).
+pub fn wrap_screen(sequences: &str) -> String {
+    const MAX_PIECE: usize = 768;
+    let mut out = String::new();
+    let mut piece = String::new();
+    for ch in sequences.chars() {
+        if piece.len() + ch.len_utf8() > MAX_PIECE {
+            out.push_str(&format!("\x1BP{}\x1B\\", piece));
+            piece.clear();
+        }
+        piece.push(ch);
+        if ch == '\x1B' {
+            out.push_str(&format!("\x1BP{}\x1B\\", piece));
+            piece.clear();
+        }
+    }
+    if !piece.is_empty() {
+        out.push_str(&format!("\x1BP{}\x1B\\", piece));
+    }
+    out
+}
+
+pub fn encode_png(img: &RgbaImage) -> Vec<u8> {
+    let mut out = Vec::new();
+    PngEncoder::new(&mut out)
+        .encode(img.as_raw(), img.width(), img.height(), ColorType::Rgba8)
+        .expect("error encoding png");
+    out
+}
@@ -0,0 +1,69 @@
+use crate::arguments::config::{ColorSample, Config};
+use crate::luminance::get_luminance_by;
+use crate::operations::animation::print_image;
+use crate::operations::threshold::get_thresholds;
+use crate::utils::{colorize, colorize_fg_bg, get_block_colors, reduce_cell_colors, reduce_colors};
+use image::{GrayImage, RgbaImage};
+
+/* Image to half blocks:
+
+- loop on the image buffer by 2x2 chuncks
+- the top row of the chunck is the upper half of the cell,
+  the bottom row is the lower half
+- colored: print '▀' with the top color as foreground
+  and the bottom color as background
+- not colored: threshold each half and print one of ' ', '▀', '▄', '█'
+*/
+
+pub fn img_to_halfblocks(config: Config) {
+    print_image(&config, translate_frame);
+}
+
+fn get_colored_char(img: &RgbaImage, config: &Config, x: u32, y: u32) -> String {
+    let top = reduce_cell_colors(&get_block_colors(img, x, y, 2, 1), config);
+    let bottom = reduce_cell_colors(&get_block_colors(img, x, y + 1, 2, 1), config);
+    match (top, bottom) {
+        (Some(top), Some(bottom)) => colorize_fg_bg(&top, &bottom, '▀', config.colors),
+        (Some(top), None) => colorize(&top, '▀', 38, config.colors),
+        (None, Some(bottom)) => colorize(&bottom, '▄', 38, config.colors),
+        (None, None) => " ".to_string(),
+    }
+}
+
+fn get_char(img: &RgbaImage, config: &Config, thresholds: &GrayImage, x: u32, y: u32) -> char {
+    let is_raised =
+        |y: u32| match reduce_colors(&get_block_colors(img, x, y, 2, 1), ColorSample::Average) {
+            Some([r, g, b]) => {
+                let threshold = (thresholds.get_pixel(x, y).0[0] as f32
+                    + thresholds.get_pixel(x + 1, y).0[0] as f32)
+                    / 2.0;
+                get_luminance_by(config, r, g, b) > threshold
+            }
+            None => false,
+        };
+    let top = is_raised(y);
+    let bottom = is_raised(y + 1);
+    match (top, bottom) {
+        (true, true) => '█',
+        (true, false) => '▀',
+        (false, true) => '▄',
+        (false, false) => ' ',
+    }
+}
+
+fn translate_frame(img: &RgbaImage, config: &Config) -> String {
+    let mut out = String::new();
+    let thresholds = get_thresholds(img, config);
+
+    for y in (0..img.height().saturating_sub(1)).step_by(2) {
+        for x in (0..img.width().saturating_sub(1)).step_by(2) {
+            if config.colored {
+                out.push_str(&get_colored_char(img, config, x, y));
+            } else {
+                out.push(get_char(img, config, &thresholds, x, y));
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
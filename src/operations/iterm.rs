@@ -0,0 +1,61 @@
+use crate::arguments::config::Config;
+use crate::operations::animation::print_image_by;
+use crate::operations::graphics::{
+    encode_png, get_cell_size, get_cell_size_of, get_factor, passthrough,
+};
+
+/* Image to iterm2 inline image:
+   source: https://iterm2.com/documentation-images.html
+
+- encode the image as png and then base64
+- send it in one "OSC 1337" sequence:
+  ESC ] 1337 ; File=inline=1;size=BYTES;width=COLUMNS;height=ROWS : DATA BEL
+  the width and height are in cells, same cells as the character styles.
+- gifs are sent as they are, the terminal plays them by itself
+  (it doesn't support --once or --sleep).
+*/
+
+pub fn img_to_iterm(config: Config) {
+    // the real image is shown, so it's always colored
+    let config = Config {
+        colored: true,
+        ..config
+    };
+    if config.image_file.ends_with(".gif") {
+        print_animated_image(&config);
+    } else {
+        print_image_by(&config, get_factor(&config), |img, _| {
+            let (columns, rows) = get_cell_size(img);
+            passthrough(&encode_iterm(&encode_png(img), columns, rows))
+        });
+    }
+}
+
+// the escape sequence that shows an image file (png, gif, ..) in
+// (columns * rows) cells
+pub fn encode_iterm(data: &[u8], columns: u32, rows: u32) -> String {
+    format!(
+        "\x1B]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=0:{}\x07",
+        data.len(),
+        columns,
+        rows,
+        base64::encode(data)
+    )
+}
+
+fn print_animated_image(config: &Config) {
+    let (data, (width, height)) = match (
+        std::fs::read(&config.image_file),
+        image::image_dimensions(&config.image_file),
+    ) {
+        (Ok(data), Ok(size)) => (data, size),
+        _ => {
+            eprintln!(
+                "Image path is not correct, OR image format is not supported!\n try -h | --help"
+            );
+            return;
+        }
+    };
+    let (columns, rows) = get_cell_size_of(config, width, height);
+    println!("{}", passthrough(&encode_iterm(&data, columns, rows)));
+}
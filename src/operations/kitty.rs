@@ -0,0 +1,121 @@
+use crate::arguments::config::Config;
+use crate::operations::animation::print_image_by;
+use crate::operations::dither::dither_image;
+use crate::operations::graphics::{encode_png, get_cell_size, get_factor, passthrough};
+use crate::utils::resize_by;
+use image::{gif::GifDecoder, AnimationDecoder, DynamicImage, RgbaImage};
+use std::fs::File;
+
+/* Image to kitty graphics:
+   source: https://sw.kovidgoyal.net/kitty/graphics-protocol/
+
+- encode the image as png and then base64
+- send it in chunks of 4096 bytes, every chunk is an "APC" sequence
+  (ESC _ G KEYS ; DATA ESC \), "m=1" means more chunks are coming
+- the first chunk has the keys: transmit and display (a=T), png (f=100),
+  the columns and rows of cells to place the image in (c, r), and no
+  replies from the terminal (q=2)
+- for animations the first frame is sent like an image, every next frame
+  is added to it (a=f) and the terminal plays them (a=a), the frames
+  gap (z) is the --sleep delay.
+*/
+
+const CHUNK_SIZE: usize = 4096;
+
+pub fn img_to_kitty(config: Config) {
+    // the real image is shown, so it's always colored
+    let config = Config {
+        colored: true,
+        ..config
+    };
+    if config.image_file.ends_with(".gif") {
+        print_animated_image(&config);
+    } else {
+        print_image_by(&config, get_factor(&config), |img, _| {
+            let (columns, rows) = get_cell_size(img);
+            passthrough(&encode_kitty(img, columns, rows))
+        });
+    }
+}
+
+// the escape sequences that show the image in (columns * rows) cells
+pub fn encode_kitty(img: &RgbaImage, columns: u32, rows: u32) -> String {
+    let keys = format!("a=T,f=100,c={},r={},q=2", columns, rows);
+    send_chunks(&keys, &encode_png(img))
+}
+
+// the escape sequences that show and play the frames in (columns * rows)
+// cells, the animation loops forever unless once is true.
+pub fn encode_kitty_animation(
+    frames: &[RgbaImage],
+    columns: u32,
+    rows: u32,
+    gap: u64,
+    once: bool,
+) -> String {
+    let mut out = String::new();
+    // an id for the image, so the frames are added to it
+    let id = std::process::id() % 0xFFFFFF + 1;
+    for (i, frame) in frames.iter().enumerate() {
+        let keys = if i == 0 {
+            format!("a=T,f=100,i={},c={},r={},q=2", id, columns, rows)
+        } else {
+            format!("a=f,f=100,i={},z={},q=2", id, gap)
+        };
+        out.push_str(&send_chunks(&keys, &encode_png(frame)));
+    }
+    // the gap of the first frame, and start playing (s=3),
+    // the loops (v) are 1 for infinite or the number of loops + 1.
+    let loops = if once { 2 } else { 1 };
+    out.push_str(&format!("\x1B_Ga=a,i={},r=1,z={},q=2\x1B\\", id, gap));
+    out.push_str(&format!("\x1B_Ga=a,i={},s=3,v={},q=2\x1B\\", id, loops));
+    out
+}
+
+fn send_chunks(keys: &str, data: &[u8]) -> String {
+    let encoded = base64::encode(data);
+    let chunks = encoded
+        .as_bytes()
+        .chunks(CHUNK_SIZE)
+        .collect::<Vec<&[u8]>>();
+    let mut out = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = (i + 1 < chunks.len()) as u8;
+        let chunk = std::str::from_utf8(chunk).unwrap();
+        if i == 0 {
+            out.push_str(&format!("\x1B_G{},m={};{}\x1B\\", keys, more, chunk));
+        } else {
+            out.push_str(&format!("\x1B_Gm={};{}\x1B\\", more, chunk));
+        }
+    }
+    out
+}
+
+fn print_animated_image(config: &Config) {
+    let file_in = match File::open(&config.image_file) {
+        Ok(file) => file,
+        Err(_) => return,
+    };
+    let decoder = GifDecoder::new(file_in).unwrap();
+    let frames = decoder
+        .into_frames()
+        .collect_frames()
+        .expect("error decoding gif");
+    let frames = frames
+        .into_iter()
+        .map(|frame| {
+            let img = DynamicImage::ImageRgba8(frame.into_buffer());
+            let mut img = resize_by(img, config, get_factor(config));
+            if config.dither {
+                dither_image(&mut img, config);
+            }
+            img
+        })
+        .collect::<Vec<RgbaImage>>();
+    if frames.is_empty() {
+        return;
+    }
+    let (columns, rows) = get_cell_size(&frames[0]);
+    let animation = encode_kitty_animation(&frames, columns, rows, config.sleep, config.once);
+    println!("{}", passthrough(&animation));
+}
@@ -1,5 +1,18 @@
+pub mod animation;
 pub mod ascii;
 pub mod braille;
+pub mod cell_fit;
 pub mod dither;
+pub mod edges;
+pub mod filters;
+pub mod graphics;
+pub mod halfblocks;
+pub mod iterm;
+pub mod kitty;
+pub mod mosaic;
 pub mod onechar;
-pub mod otsu_threshold;
+pub mod palette;
+pub mod shapes;
+pub mod sixel;
+pub mod sobel;
+pub mod threshold;
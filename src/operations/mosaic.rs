@@ -0,0 +1,248 @@
+use crate::arguments::config::Config;
+use crate::luminance::{get_luminance, get_luminance_by};
+use crate::operations::animation::{print_image, print_image_by};
+use crate::operations::threshold::get_thresholds;
+use crate::utils::{colorize, colorize_fg_bg, is_transparent, reduce_cell_colors};
+use image::{GrayImage, RgbaImage};
+
+/* Image to quadrants/sextants/octants:
+   source: https://en.wikipedia.org/wiki/Block_Elements
+           https://en.wikipedia.org/wiki/Symbols_for_Legacy_Computing
+
+- loop on the image buffer by chuncks of (2*2) for quadrants, (2*3) for sextants
+  or (2*4) for octants
+- collect the sub pixels of the chunck and decide which ones are "lit"
+  (brighter than the threshold), same as the braille dots
+- build a bitmask from the lit sub pixels in row-major order:
+      ---------
+      | 0 | 1 |
+      | 2 | 3 |
+      | 4 | 5 |
+      | 6 | 7 |
+      ---------
+- the bitmask selects the glyph, in colored mode the lit sub pixels are the
+  foreground color and the rest is the background color.
+*/
+
+// quadrant glyphs indexed by the bitmask
+pub const QUADRANTS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+// octant patterns that are not in the octants block (U+1CD00) because they
+// already exist as other block characters, sorted by the bitmask.
+const OCTANT_EXCEPTIONS: [(u8, char); 26] = [
+    (0, ' '),
+    (1, '\u{1CEA8}'),
+    (2, '\u{1CEAB}'),
+    (3, '\u{1FB82}'),
+    (5, '▘'),
+    (10, '▝'),
+    (15, '▀'),
+    (20, '\u{1FBE6}'),
+    (40, '\u{1FBE7}'),
+    (63, '\u{1FB85}'),
+    (64, '\u{1CEA3}'),
+    (80, '▖'),
+    (85, '▌'),
+    (90, '▞'),
+    (95, '▛'),
+    (128, '\u{1CEA0}'),
+    (160, '▗'),
+    (165, '▚'),
+    (170, '▐'),
+    (175, '▜'),
+    (192, '▂'),
+    (240, '▄'),
+    (245, '▙'),
+    (250, '▟'),
+    (252, '▆'),
+    (255, '█'),
+];
+
+pub fn img_to_quadrants(config: Config) {
+    print_image(&config, |img, config| {
+        translate_frame(img, config, 2, quadrant)
+    });
+}
+
+// the (2*3) cells are 1.5 times taller than the (2*2) quadrants,
+// so the buffer is made taller to keep the same number of rows
+pub fn img_to_sextants(config: Config) {
+    print_image_by(&config, (1.0, 1.5), |img, config| {
+        translate_frame(img, config, 3, sextant)
+    });
+}
+
+// the (2*4) cells are twice as tall as the quadrants
+pub fn img_to_octants(config: Config) {
+    print_image_by(&config, (1.0, 2.0), |img, config| {
+        translate_frame(img, config, 4, octant)
+    });
+}
+
+fn quadrant(mask: u8) -> char {
+    QUADRANTS[mask as usize]
+}
+
+// sextants start at U+1FB00 and skip the four patterns that already exist
+// as other characters (empty, left half, right half and full block).
+fn sextant(mask: u8) -> char {
+    match mask {
+        0 => ' ',
+        21 => '▌',
+        42 => '▐',
+        63 => '█',
+        _ => {
+            let skipped = 1 + (mask > 21) as u32 + (mask > 42) as u32;
+            std::char::from_u32(0x1FB00 + mask as u32 - skipped).unwrap()
+        }
+    }
+}
+
+// octants (unicode 16) start at U+1CD00 and skip the exceptions above
+fn octant(mask: u8) -> char {
+    let mut skipped = 0;
+    for (exception, ch) in OCTANT_EXCEPTIONS.iter() {
+        if *exception == mask {
+            return *ch;
+        }
+        if *exception < mask {
+            skipped += 1;
+        }
+    }
+    std::char::from_u32(0x1CD00 + mask as u32 - skipped).unwrap()
+}
+
+// the sub pixels of the chunck in row-major order, None for transparent ones.
+fn get_sub_pixels(img: &RgbaImage, x: u32, y: u32, rows: u32) -> Vec<Option<[u8; 3]>> {
+    let mut out = Vec::new();
+    for iy in y..y + rows {
+        for ix in x..x + 2 {
+            let [r, g, b, a] = img.get_pixel(ix, iy).0;
+            if is_transparent([r, g, b, a]) {
+                out.push(None);
+            } else {
+                out.push(Some([r, g, b]));
+            }
+        }
+    }
+    out
+}
+
+// the thresholds of the sub pixels in the same order as get_sub_pixels
+fn get_sub_thresholds(thresholds: &GrayImage, x: u32, y: u32, rows: u32) -> Vec<u8> {
+    let mut out = Vec::new();
+    for iy in y..y + rows {
+        for ix in x..x + 2 {
+            out.push(thresholds.get_pixel(ix, iy).0[0]);
+        }
+    }
+    out
+}
+
+// colored cells are split by the average luminance of the chunck itself,
+// so every chunck gets the best two colors it can show.
+fn get_colored_char(
+    sub_pixels: &[Option<[u8; 3]>],
+    config: &Config,
+    glyph: fn(u8) -> char,
+) -> String {
+    let opaque = sub_pixels.iter().flatten().collect::<Vec<_>>();
+    if opaque.is_empty() {
+        return " ".to_string();
+    }
+    let lumi = |[r, g, b]: [u8; 3]| get_luminance(config.luma, r, g, b);
+    let mean = opaque.iter().map(|color| lumi(**color)).sum::<f32>() / opaque.len() as f32;
+
+    let mut mask = 0u8;
+    let mut fg = Vec::new();
+    let mut bg = Vec::new();
+    for (i, sub_pixel) in sub_pixels.iter().enumerate() {
+        match sub_pixel {
+            Some(color) if lumi(*color) >= mean => {
+                mask |= 1 << i;
+                fg.push(*color);
+            }
+            Some(color) => bg.push(*color),
+            None => {}
+        }
+    }
+    let has_transparent = opaque.len() < sub_pixels.len();
+    let fg = reduce_cell_colors(&fg, config).unwrap();
+    match reduce_cell_colors(&bg, config) {
+        // transparent sub pixels are left for the terminal's background
+        Some(bg) if !has_transparent => colorize_fg_bg(&fg, &bg, glyph(mask), config.colors),
+        _ => colorize(&fg, glyph(mask), 38, config.colors),
+    }
+}
+
+fn get_char(
+    sub_pixels: &[Option<[u8; 3]>],
+    config: &Config,
+    thresholds: &[u8],
+    glyph: fn(u8) -> char,
+) -> char {
+    let mut mask = 0u8;
+    for (i, (sub_pixel, threshold)) in sub_pixels.iter().zip(thresholds.iter()).enumerate() {
+        if let Some([r, g, b]) = sub_pixel {
+            if get_luminance_by(config, *r, *g, *b) > *threshold as f32 {
+                mask |= 1 << i;
+            }
+        }
+    }
+    glyph(mask)
+}
+
+fn translate_frame(img: &RgbaImage, config: &Config, rows: u32, glyph: fn(u8) -> char) -> String {
+    let mut out = String::new();
+    let thresholds = get_thresholds(img, config);
+
+    for y in (0..(img.height() + 1).saturating_sub(rows)).step_by(rows as usize) {
+        for x in (0..img.width().saturating_sub(1)).step_by(2) {
+            let sub_pixels = get_sub_pixels(img, x, y, rows);
+            if config.colored {
+                out.push_str(&get_colored_char(&sub_pixels, config, glyph));
+            } else {
+                let sub_thresholds = get_sub_thresholds(&thresholds, x, y, rows);
+                out.push(get_char(&sub_pixels, config, &sub_thresholds, glyph));
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn sextants_skip_the_half_blocks() {
+        assert_eq!(sextant(0), ' ');
+        assert_eq!(sextant(1), '\u{1FB00}');
+        assert_eq!(sextant(20), '\u{1FB13}');
+        assert_eq!(sextant(21), '▌');
+        assert_eq!(sextant(22), '\u{1FB14}');
+        assert_eq!(sextant(42), '▐');
+        assert_eq!(sextant(62), '\u{1FB3B}');
+        assert_eq!(sextant(63), '█');
+        assert_eq!((0..64).map(sextant).collect::<HashSet<_>>().len(), 64);
+    }
+
+    #[test]
+    fn octants_skip_the_exceptions() {
+        assert_eq!(octant(0), ' ');
+        assert_eq!(octant(4), '\u{1CD00}');
+        assert_eq!(octant(5), '▘');
+        assert_eq!(octant(6), '\u{1CD01}');
+        assert_eq!(octant(7), '\u{1CD02}');
+        assert_eq!(octant(254), '\u{1CDE5}');
+        assert_eq!(octant(255), '█');
+        for (mask, ch) in OCTANT_EXCEPTIONS.iter() {
+            assert_eq!(octant(*mask), *ch);
+        }
+        assert_eq!((0..=255).map(octant).collect::<HashSet<_>>().len(), 256);
+    }
+}
@@ -1,24 +1,29 @@
 use crate::arguments::config::Config;
-use crate::operations::otsu_threshold::OtsuThreshold;
-use crate::utils::get_luma_buffer;
+use crate::luminance::get_luma_image;
+use crate::operations::threshold::Threshold;
+use crate::utils::{is_transparent, open_and_resize};
 use image::Luma;
 
 //  will make the image to ONLY black and white
 //  by converting the the "grays" to black or white based on the scale.
 // source: https://en.wikipedia.org/wiki/Thresholding_(image_processing)
-// below we are using Otsu's thresholding which is automatically finds
-// the best threshold value
+// by default we are using Otsu's thresholding which is automatically finds
+// the best threshold value, check threshold.rs for the other methods
 // https://en.wikipedia.org/wiki/Otsu%27s_method
 pub fn img_to_onechar(config: Config) {
-    let mut img: image::ImageBuffer<image::Luma<u8>, Vec<u8>> = match get_luma_buffer(&config) {
+    let rgba_img = match open_and_resize(&config) {
         Some(img) => img,
         None => return,
     };
-    img.threshold();
+    // keeping the rgba image around to know which pixels are transparent.
+    let mut img = get_luma_image(&rgba_img, &config);
+    img.threshold(config.threshold, config.threshold_window, Some(&rgba_img));
     for y in 0..img.height() {
         for x in 0..img.width() {
             let pixel = img.get_pixel(x, y);
-            if *pixel == Luma([255]) {
+            if is_transparent(rgba_img.get_pixel(x, y).0) {
+                print!(" ");
+            } else if *pixel == Luma([255]) {
                 print!("{}", config.onechar);
             } else {
                 print!(" ");
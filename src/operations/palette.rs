@@ -0,0 +1,127 @@
+use crate::arguments::argument_parsing::parse_color;
+use crate::color::{get_closest_index, srgb_to_oklab};
+use crate::utils::is_transparent;
+use image::RgbaImage;
+
+/* Palettes:
+
+the image can be drawn with a few colors only (like old consoles), every
+pixel is changed to the closest color of the palette after resizing, or
+with --dither the difference is spread to the neighbour pixels.
+a palette is one of the built-in ones, or a file:
+- GIMP palette (.gpl): "GIMP Palette" then a "R G B name" line for every color,
+  every line starting with '#' is a comment ("#Palette Name: ..")
+- a list of hex colors, one on every line ("#ff8800" or "ff8800"),
+  lines starting with '#' followed by a space are comments
+*/
+
+// (name, colors) pairs
+pub const PALETTES: [(&str, &[u32]); 5] = [
+    ("gameboy", &[0x0f380f, 0x306230, 0x8bac0f, 0x9bbc0f]),
+    ("cga", &[0x000000, 0x55ffff, 0xff55ff, 0xffffff]),
+    (
+        "ega",
+        &[
+            0x000000, 0x0000aa, 0x00aa00, 0x00aaaa, 0xaa0000, 0xaa00aa, 0xaa5500, 0xaaaaaa,
+            0x555555, 0x5555ff, 0x55ff55, 0x55ffff, 0xff5555, 0xff55ff, 0xffff55, 0xffffff,
+        ],
+    ),
+    (
+        "solarized",
+        &[
+            0x002b36, 0x073642, 0x586e75, 0x657b83, 0x839496, 0x93a1a1, 0xeee8d5, 0xfdf6e3,
+            0xb58900, 0xcb4b16, 0xdc322f, 0xd33682, 0x6c71c4, 0x268bd2, 0x2aa198, 0x859900,
+        ],
+    ),
+    (
+        "nord",
+        &[
+            0x2e3440, 0x3b4252, 0x434c5e, 0x4c566a, 0xd8dee9, 0xe5e9f0, 0xeceff4, 0x8fbcbb,
+            0x88c0d0, 0x81a1c1, 0x5e81ac, 0xbf616a, 0xd08770, 0xebcb8b, 0xa3be8c, 0xb48ead,
+        ],
+    ),
+];
+
+pub fn get_names() -> Vec<&'static str> {
+    PALETTES.iter().map(|(name, _)| *name).collect()
+}
+
+// a built-in palette by its name, or a palette file
+pub fn get_palette(arg: &str) -> Result<Vec<[u8; 3]>, String> {
+    if let Some((_, colors)) = PALETTES.iter().find(|(name, _)| *name == arg) {
+        return Ok(colors
+            .iter()
+            .map(|color| [(color >> 16) as u8, (color >> 8) as u8, *color as u8])
+            .collect());
+    }
+    let text = std::fs::read_to_string(arg).map_err(|e| format!("{}: {}", arg, e))?;
+    let is_gpl = text.trim_start().starts_with("GIMP Palette");
+    let mut colors = Vec::new();
+    for line in text.lines().map(|line| line.trim()) {
+        let is_comment = match is_gpl {
+            true => line.starts_with('#'),
+            false => line.starts_with("# ") || line == "#",
+        };
+        if line.is_empty()
+            || is_comment
+            || line.starts_with("GIMP Palette")
+            || line.starts_with("Name:")
+            || line.starts_with("Columns:")
+        {
+            continue;
+        }
+        let color = match is_gpl {
+            true => parse_gpl_line(line),
+            // the name after a hex color is allowed too
+            false => line.split_whitespace().next().and_then(parse_color),
+        };
+        match color {
+            Some(color) => colors.push(color),
+            None => return Err(format!("{}: not a valid color \"{}\"", arg, line)),
+        }
+    }
+    if colors.is_empty() {
+        return Err(format!("{}: the palette has no colors", arg));
+    }
+    Ok(colors)
+}
+
+// a "R G B name" line of a gpl file
+fn parse_gpl_line(line: &str) -> Option<[u8; 3]> {
+    let values = line
+        .split_whitespace()
+        .take(3)
+        .map(|value| value.parse::<u8>().ok())
+        .collect::<Option<Vec<u8>>>();
+    match values.as_deref() {
+        Some([r, g, b]) => Some([*r, *g, *b]),
+        _ => None,
+    }
+}
+
+// the palette colors in Oklab, to convert them once and not for every pixel
+pub fn get_palette_oklab(palette: &[[u8; 3]]) -> Vec<[f32; 3]> {
+    palette.iter().map(srgb_to_oklab).collect()
+}
+
+// the closest color of the palette, palette_oklab is from get_palette_oklab
+pub fn get_closest_color(
+    rgb: &[u8; 3],
+    palette: &[[u8; 3]],
+    palette_oklab: &[[f32; 3]],
+) -> [u8; 3] {
+    palette[get_closest_index(&srgb_to_oklab(rgb), palette_oklab)]
+}
+
+// change every pixel to the closest color of the palette
+pub fn quantize(img: &mut RgbaImage, palette: &[[u8; 3]]) {
+    let palette_oklab = get_palette_oklab(palette);
+    for pixel in img.pixels_mut() {
+        if is_transparent(pixel.0) {
+            continue;
+        }
+        let [r, g, b, _] = pixel.0;
+        let color = get_closest_color(&[r, g, b], palette, &palette_oklab);
+        pixel.0[..3].clone_from_slice(&color);
+    }
+}
@@ -0,0 +1,296 @@
+use crate::arguments::config::Config;
+use crate::luminance::get_luminance_by;
+use crate::operations::animation::print_image_by;
+use crate::utils::{colorize, get_cell_color, is_transparent};
+use image::RgbaImage;
+
+/* Image to ascii by shape:
+
+the ascii style picks a character only by the brightness of the chunck, so the
+edges and lines of the image are lost, this style picks it by its shape:
+
+- loop on the image buffer by (4*8) chuncks (the image is resized 2x wider
+  and 4x taller than the ascii style, so the output has the same size)
+- if the chunck is flat (low contrast) pick a character by its brightness
+- otherwise split the chunck into "ink" (brighter than the middle of the chunck)
+  and background, and turn it into a bitmask
+- compare the bitmask with the glyph bitmaps below and pick the glyph
+  with the least different pixels
+*/
+
+const CELL_WIDTH: u32 = 4;
+const CELL_HEIGHT: u32 = 8;
+
+// chuncks with contrast (max - min luminance) below this are treated as flat
+const MIN_CONTRAST: f32 = 48.0;
+
+// used for the flat chuncks when --table is not given
+const FLAT_TABLE: [char; 10] = [' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+// (4*8) glyph bitmaps, '#' is ink
+pub const GLYPHS: [(char, [&str; 8]); 30] = [
+    (
+        ' ',
+        [
+            "....", "....", "....", "....", "....", "....", "....", "....",
+        ],
+    ),
+    (
+        '.',
+        [
+            "....", "....", "....", "....", "....", "....", ".##.", ".##.",
+        ],
+    ),
+    (
+        ',',
+        [
+            "....", "....", "....", "....", "....", ".##.", "..#.", ".#..",
+        ],
+    ),
+    (
+        '\'',
+        [
+            ".##.", ".##.", "....", "....", "....", "....", "....", "....",
+        ],
+    ),
+    (
+        '"',
+        [
+            "#..#", "#..#", "....", "....", "....", "....", "....", "....",
+        ],
+    ),
+    (
+        ':',
+        [
+            "....", ".##.", ".##.", "....", "....", ".##.", ".##.", "....",
+        ],
+    ),
+    (
+        '-',
+        [
+            "....", "....", "....", "####", "####", "....", "....", "....",
+        ],
+    ),
+    (
+        '_',
+        [
+            "....", "....", "....", "....", "....", "....", "....", "####",
+        ],
+    ),
+    (
+        '=',
+        [
+            "....", "....", "####", "....", "....", "####", "....", "....",
+        ],
+    ),
+    (
+        '|',
+        [
+            ".##.", ".##.", ".##.", ".##.", ".##.", ".##.", ".##.", ".##.",
+        ],
+    ),
+    (
+        '/',
+        [
+            "...#", "...#", "..#.", "..#.", ".#..", ".#..", "#...", "#...",
+        ],
+    ),
+    (
+        '\\',
+        [
+            "#...", "#...", ".#..", ".#..", "..#.", "..#.", "...#", "...#",
+        ],
+    ),
+    (
+        '(',
+        [
+            "..#.", ".#..", "#...", "#...", "#...", "#...", ".#..", "..#.",
+        ],
+    ),
+    (
+        ')',
+        [
+            ".#..", "..#.", "...#", "...#", "...#", "...#", "..#.", ".#..",
+        ],
+    ),
+    (
+        '[',
+        [
+            "##..", "#...", "#...", "#...", "#...", "#...", "#...", "##..",
+        ],
+    ),
+    (
+        ']',
+        [
+            "..##", "...#", "...#", "...#", "...#", "...#", "...#", "..##",
+        ],
+    ),
+    (
+        '<',
+        [
+            "....", "..##", ".#..", "#...", "#...", ".#..", "..##", "....",
+        ],
+    ),
+    (
+        '>',
+        [
+            "....", "##..", "..#.", "...#", "...#", "..#.", "##..", "....",
+        ],
+    ),
+    (
+        '^',
+        [
+            ".##.", "#..#", "....", "....", "....", "....", "....", "....",
+        ],
+    ),
+    (
+        'v',
+        [
+            "....", "....", "....", "#..#", "#..#", ".##.", ".##.", "....",
+        ],
+    ),
+    (
+        '+',
+        [
+            "....", ".##.", ".##.", "####", "####", ".##.", ".##.", "....",
+        ],
+    ),
+    (
+        'x',
+        [
+            "....", "....", "#..#", ".##.", ".##.", "#..#", "....", "....",
+        ],
+    ),
+    (
+        'o',
+        [
+            "....", "....", ".##.", "#..#", "#..#", ".##.", "....", "....",
+        ],
+    ),
+    (
+        'T',
+        [
+            "####", ".##.", ".##.", ".##.", ".##.", ".##.", ".##.", ".##.",
+        ],
+    ),
+    (
+        'L',
+        [
+            "#...", "#...", "#...", "#...", "#...", "#...", "#...", "####",
+        ],
+    ),
+    (
+        'J',
+        [
+            "...#", "...#", "...#", "...#", "...#", "...#", "...#", "####",
+        ],
+    ),
+    (
+        '7',
+        [
+            "####", "...#", "..#.", "..#.", ".#..", ".#..", ".#..", ".#..",
+        ],
+    ),
+    (
+        'P',
+        [
+            "###.", "#..#", "#..#", "###.", "#...", "#...", "#...", "#...",
+        ],
+    ),
+    (
+        'M',
+        [
+            "#..#", "####", "####", "#..#", "#..#", "#..#", "#..#", "#..#",
+        ],
+    ),
+    (
+        '@',
+        [
+            ".##.", "#..#", "#.##", "#.##", "#.##", "#...", ".##.", "....",
+        ],
+    ),
+];
+
+pub fn img_to_shapes(config: Config) {
+    print_image_by(&config, (2.0, 4.0), translate_frame);
+}
+
+// turn the glyph bitmap to a bitmask, bit (row * 4 + column) is set for ink
+pub fn get_glyph_mask(rows: &[&str; 8]) -> u32 {
+    let mut mask = 0;
+    for (iy, row) in rows.iter().enumerate() {
+        for (ix, ch) in row.chars().enumerate() {
+            if ch == '#' {
+                mask |= 1 << (iy * CELL_WIDTH as usize + ix);
+            }
+        }
+    }
+    mask
+}
+
+fn get_char(img: &RgbaImage, config: &Config, masks: &[(char, u32)], x: u32, y: u32) -> String {
+    let mut lumis = Vec::new();
+    for iy in y..y + CELL_HEIGHT {
+        for ix in x..x + CELL_WIDTH {
+            let [r, g, b, a] = img.get_pixel(ix, iy).0;
+            lumis.push(if is_transparent([r, g, b, a]) {
+                None
+            } else {
+                Some(get_luminance_by(config, r, g, b))
+            });
+        }
+    }
+    let opaque = lumis.iter().flatten().copied().collect::<Vec<f32>>();
+    // a mostly transparent chunck is left blank without colors
+    if opaque.len() < lumis.len() / 2 {
+        return " ".to_string();
+    }
+    let min = opaque.iter().copied().fold(f32::MAX, f32::min);
+    let max = opaque.iter().copied().fold(f32::MIN, f32::max);
+
+    let cha = if max - min < MIN_CONTRAST {
+        let avg = opaque.iter().sum::<f32>() / opaque.len() as f32;
+        let table = if config.table.is_empty() {
+            &FLAT_TABLE[..]
+        } else {
+            &config.table[..]
+        };
+        table[(avg / 255.0 * ((table.len() - 1) as f32)) as usize]
+    } else {
+        let middle = (min + max) / 2.0;
+        let mut pattern = 0u32;
+        for (i, lumi) in lumis.iter().enumerate() {
+            if let Some(lumi) = lumi {
+                if *lumi > middle {
+                    pattern |= 1 << i;
+                }
+            }
+        }
+        masks
+            .iter()
+            .min_by_key(|(_, mask)| (pattern ^ mask).count_ones())
+            .map(|(ch, _)| *ch)
+            .unwrap()
+    };
+    if config.colored {
+        let rgb = get_cell_color(img, config, x, y, CELL_WIDTH, CELL_HEIGHT);
+        colorize(&rgb, cha, config.background, config.colors)
+    } else {
+        cha.to_string()
+    }
+}
+
+fn translate_frame(img: &RgbaImage, config: &Config) -> String {
+    let masks = GLYPHS
+        .iter()
+        .map(|(ch, rows)| (*ch, get_glyph_mask(rows)))
+        .collect::<Vec<(char, u32)>>();
+    let mut out = String::new();
+    for y in (0..(img.height() + 1).saturating_sub(CELL_HEIGHT)).step_by(CELL_HEIGHT as usize) {
+        for x in (0..(img.width() + 1).saturating_sub(CELL_WIDTH)).step_by(CELL_WIDTH as usize) {
+            out.push_str(&get_char(img, config, &masks, x, y));
+        }
+        out.push('\n');
+    }
+    out
+}
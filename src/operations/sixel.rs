@@ -0,0 +1,125 @@
+use crate::arguments::config::Config;
+use crate::operations::animation::print_image_by;
+use crate::operations::graphics::{get_factor, passthrough};
+use crate::utils::is_transparent;
+use color_quant::NeuQuant;
+use image::RgbaImage;
+
+/* Image to sixel:
+   source: https://vt100.net/docs/vt3xx-gp/chapter14.html
+
+- reduce the colors of the image to a palette of 256 colors (NeuQuant)
+- start the sixel sequence and define the palette colors in percent
+- loop on the image by bands of 6 rows, for every color used in the band
+  select it and write a character for every column, the character is
+  63 + the bits of the 6 pixels (top pixel is the lowest bit) that have
+  this color, then go back to the start of the band ("$")
+- repeated characters are written once with their count ("!COUNT CHAR")
+- go to the next band ("-") and end the sequence
+transparent pixels are never drawn so the terminal's background shows.
+*/
+
+const PALETTE_SIZE: usize = 256;
+// 1 is the best quality and 30 the fastest
+const SAMPLE_FACTOR: i32 = 10;
+
+pub fn img_to_sixel(config: Config) {
+    // sixel shows the real image, so it's always colored
+    let config = Config {
+        colored: true,
+        ..config
+    };
+    print_image_by(&config, get_factor(&config), |img, _| {
+        passthrough(&encode_sixel(img))
+    });
+}
+
+pub fn encode_sixel(img: &RgbaImage) -> String {
+    let (width, height) = img.dimensions();
+    let opaque = img
+        .pixels()
+        .filter(|pixel| !is_transparent(pixel.0))
+        .flat_map(|pixel| [pixel.0[0], pixel.0[1], pixel.0[2], 255])
+        .collect::<Vec<u8>>();
+    if opaque.is_empty() {
+        return String::new();
+    }
+    let quantizer = NeuQuant::new(SAMPLE_FACTOR, PALETTE_SIZE, &opaque);
+    let indexes = img
+        .pixels()
+        .map(|pixel| {
+            let [r, g, b, a] = pixel.0;
+            if is_transparent([r, g, b, a]) {
+                None
+            } else {
+                Some(quantizer.index_of(&[r, g, b, 255]))
+            }
+        })
+        .collect::<Vec<Option<usize>>>();
+
+    // "P2 = 1" leaves the pixels without color as they are (transparent)
+    let mut out = format!("\x1BP0;1;0q\"1;1;{};{}", width, height);
+    for (i, color) in quantizer.color_map_rgb().chunks(3).enumerate() {
+        let percent = |c: u8| (c as u32 * 100 + 127) / 255;
+        out.push_str(&format!(
+            "#{};2;{};{};{}",
+            i,
+            percent(color[0]),
+            percent(color[1]),
+            percent(color[2])
+        ));
+    }
+
+    for band in (0..height).step_by(6) {
+        let rows = (height - band).min(6);
+        let mut used = [false; PALETTE_SIZE];
+        for y in band..band + rows {
+            for x in 0..width {
+                if let Some(index) = indexes[(y * width + x) as usize] {
+                    used[index] = true;
+                }
+            }
+        }
+        for color in (0..PALETTE_SIZE).filter(|color| used[*color]) {
+            let sixels = (0..width).map(|x| {
+                let mut bits = 0u8;
+                for row in 0..rows {
+                    if indexes[((band + row) * width + x) as usize] == Some(color) {
+                        bits |= 1 << row;
+                    }
+                }
+                (63 + bits) as char
+            });
+            out.push_str(&format!("#{}{}$", color, run_length_encode(sixels)));
+        }
+        out.push('-');
+    }
+    out.push_str("\x1B\\");
+    out
+}
+
+fn run_length_encode(sixels: impl Iterator<Item = char>) -> String {
+    let mut out = String::new();
+    let mut push_run = |ch: char, count: usize| {
+        if count > 3 {
+            out.push_str(&format!("!{}{}", count, ch));
+        } else {
+            (0..count).for_each(|_| out.push(ch));
+        }
+    };
+    let mut run: Option<(char, usize)> = None;
+    for ch in sixels {
+        run = match run {
+            Some((last, count)) if last == ch => Some((last, count + 1)),
+            Some((last, count)) => {
+                push_run(last, count);
+                Some((ch, 1))
+            }
+            None => Some((ch, 1)),
+        };
+    }
+    if let Some((last, count)) = run {
+        push_run(last, count);
+    }
+    out
+}
@@ -0,0 +1,70 @@
+use image::GrayImage;
+
+// Sobel operator, source: https://en.wikipedia.org/wiki/Sobel_operator
+// and the non-maximum suppression step from Canny's edge detector:
+// https://en.wikipedia.org/wiki/Canny_edge_detector#Gradient_magnitude_thresholding_or_lower_bound_cut-off_suppression
+
+pub trait Sobel {
+    fn get_gradients(&self) -> Vec<(f32, f32)>;
+    fn get_edges(&self) -> (GrayImage, Vec<f32>);
+}
+
+impl Sobel for GrayImage {
+    // the (x, y) gradient of every pixel in row-major order,
+    // pixels on the border of the image get a zero gradient.
+    fn get_gradients(&self) -> Vec<(f32, f32)> {
+        let (width, height) = self.dimensions();
+        let mut out = vec![(0.0, 0.0); (width * height) as usize];
+        if width < 3 || height < 3 {
+            return out;
+        }
+        let p = |x: u32, y: u32| self.get_pixel(x, y).0[0] as f32;
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                let gx = (p(x + 1, y - 1) + 2.0 * p(x + 1, y) + p(x + 1, y + 1))
+                    - (p(x - 1, y - 1) + 2.0 * p(x - 1, y) + p(x - 1, y + 1));
+                let gy = (p(x - 1, y + 1) + 2.0 * p(x, y + 1) + p(x + 1, y + 1))
+                    - (p(x - 1, y - 1) + 2.0 * p(x, y - 1) + p(x + 1, y - 1));
+                out[(y * width + x) as usize] = (gx, gy);
+            }
+        }
+        out
+    }
+
+    // returns the thin edges magnitude (0-255) and the gradient direction
+    // (degrees in 0-180) of every pixel in row-major order.
+    fn get_edges(&self) -> (GrayImage, Vec<f32>) {
+        let (width, height) = self.dimensions();
+        let gradients = self.get_gradients();
+        let magnitudes = gradients
+            .iter()
+            .map(|(gx, gy)| (gx * gx + gy * gy).sqrt())
+            .collect::<Vec<f32>>();
+        let directions = gradients
+            .iter()
+            .map(|(gx, gy)| gy.atan2(*gx).to_degrees().rem_euclid(180.0))
+            .collect::<Vec<f32>>();
+
+        let mut edges = GrayImage::new(width, height);
+        for y in 1..height.saturating_sub(1) {
+            for x in 1..width.saturating_sub(1) {
+                let i = (y * width + x) as usize;
+                // the two neighbors along the gradient direction
+                let (dx, dy): (i32, i32) = match directions[i] {
+                    d if !(22.5..157.5).contains(&d) => (1, 0),
+                    d if d < 67.5 => (1, 1),
+                    d if d < 112.5 => (0, 1),
+                    _ => (-1, 1),
+                };
+                let before = ((y as i32 - dy) as u32 * width + (x as i32 - dx) as u32) as usize;
+                let after = ((y as i32 + dy) as u32 * width + (x as i32 + dx) as u32) as usize;
+                // keeping only the local maximums, so the edges are one pixel thin
+                if magnitudes[i] > magnitudes[before] && magnitudes[i] >= magnitudes[after] {
+                    // the biggest sobel magnitude is around 4 * 255 * sqrt(2)
+                    edges.put_pixel(x, y, image::Luma([(magnitudes[i] / 4.0).min(255.0) as u8]));
+                }
+            }
+        }
+        (edges, directions)
+    }
+}
@@ -0,0 +1,147 @@
+use crate::arguments::config::{Config, ThresholdMode};
+use crate::luminance::get_luma_image;
+use crate::utils::is_transparent;
+use image::{GrayImage, Luma, RgbaImage};
+
+// thresholding: https://en.wikipedia.org/wiki/Thresholding_(image_processing)
+// otsu's method: https://en.wikipedia.org/wiki/Otsu%27s_method
+// the local methods (mean, niblack, sauvola) calculate a threshold for every
+// pixel from the mean and the standard deviation of the pixels around it,
+// so uneven lighting doesn't turn half the image black.
+// niblack: T = mean - k * deviation
+// sauvola: T = mean * (1 + k * (deviation / R - 1))
+
+// k values for niblack and sauvola, and the dynamic range of
+// the standard deviation for sauvola
+const NIBLACK_K: f64 = 0.2;
+const SAUVOLA_K: f64 = 0.2;
+const SAUVOLA_R: f64 = 128.0;
+
+// the rgba image (source) the luma buffer was made from is optional,
+// when given its transparent pixels are not counted in the histogram.
+pub trait Threshold {
+    fn get_histogram(&self, source: Option<&RgbaImage>) -> [usize; 256];
+    fn get_otsu_value(&self, source: Option<&RgbaImage>) -> u8;
+    fn get_threshold_map(
+        &self,
+        mode: ThresholdMode,
+        window: u32,
+        source: Option<&RgbaImage>,
+    ) -> GrayImage;
+    fn threshold(&mut self, mode: ThresholdMode, window: u32, source: Option<&RgbaImage>);
+}
+
+impl Threshold for GrayImage {
+    fn get_histogram(&self, source: Option<&RgbaImage>) -> [usize; 256] {
+        let mut out = [0; 256];
+        self.enumerate_pixels()
+            .filter(|(x, y, _)| match source {
+                Some(source) => !is_transparent(source.get_pixel(*x, *y).0),
+                None => true,
+            })
+            .for_each(|(_, _, p)| {
+                out[p.0[0] as usize] += 1;
+            });
+        out
+    }
+    fn get_otsu_value(&self, source: Option<&RgbaImage>) -> u8 {
+        let img_histogram: [usize; 256] = self.get_histogram(source);
+        let total_weight = img_histogram.iter().sum::<usize>() as f64;
+        let mut bg_sum = 0.0;
+        let mut bg_weight = 0.0;
+        let mut max_variance = 0.0;
+        let mut best_threshold = 0;
+        let sum_intensity: f64 = img_histogram
+            .iter()
+            .enumerate()
+            .fold(0f64, |acu, (t, c)| acu + (t * c) as f64);
+
+        for (threshold, count) in img_histogram.iter().enumerate() {
+            let fg_weight = total_weight - bg_weight;
+            if fg_weight > 0.0 && bg_weight > 0.0 {
+                let fg_mean = (sum_intensity - bg_sum) / fg_weight;
+                let val = (bg_weight * fg_weight * ((bg_sum / bg_weight) - fg_mean)).powi(2);
+                if val >= max_variance {
+                    best_threshold = threshold as u8;
+                    max_variance = val;
+                }
+            }
+            bg_weight += *count as f64;
+            bg_sum += (threshold * count) as f64;
+        }
+
+        best_threshold
+    }
+    // returns an image with the threshold value of every pixel,
+    // window is the size of the neighborhood used by the local methods.
+    fn get_threshold_map(
+        &self,
+        mode: ThresholdMode,
+        window: u32,
+        source: Option<&RgbaImage>,
+    ) -> GrayImage {
+        let global = match mode {
+            ThresholdMode::Otsu => Some(self.get_otsu_value(source)),
+            ThresholdMode::Fixed(value) => Some(value),
+            _ => None,
+        };
+        if let Some(value) = global {
+            return GrayImage::from_pixel(self.width(), self.height(), Luma([value]));
+        }
+
+        let (sums, squares) = get_integral_images(self);
+        let (width, height) = self.dimensions();
+        let half = (window / 2).max(1);
+        GrayImage::from_fn(width, height, |x, y| {
+            let (x0, y0) = (x.saturating_sub(half), y.saturating_sub(half));
+            let (x1, y1) = ((x + half + 1).min(width), (y + half + 1).min(height));
+            let count = ((x1 - x0) * (y1 - y0)) as f64;
+            let area = |table: &[f64]| {
+                let at = |x: u32, y: u32| table[(y * (width + 1) + x) as usize];
+                at(x1, y1) - at(x0, y1) - at(x1, y0) + at(x0, y0)
+            };
+            let mean = area(&sums) / count;
+            let deviation = (area(&squares) / count - mean * mean).max(0.0).sqrt();
+            let value = match mode {
+                ThresholdMode::Niblack => mean - NIBLACK_K * deviation,
+                ThresholdMode::Sauvola => mean * (1.0 + SAUVOLA_K * (deviation / SAUVOLA_R - 1.0)),
+                _ => mean,
+            };
+            Luma([value.round().clamp(0.0, 255.0) as u8])
+        })
+    }
+    fn threshold(&mut self, mode: ThresholdMode, window: u32, source: Option<&RgbaImage>) {
+        let thresholds = self.get_threshold_map(mode, window, source);
+        self.iter_mut()
+            .zip(thresholds.iter())
+            .for_each(|(p, t)| *p = if *p < *t { 0 } else { 255 });
+    }
+}
+
+// the threshold map of an image buffer using the mode the user picked
+pub fn get_thresholds(img: &RgbaImage, config: &Config) -> GrayImage {
+    get_luma_image(img, config).get_threshold_map(
+        config.threshold,
+        config.threshold_window,
+        Some(img),
+    )
+}
+
+// summed-area tables of the pixels and their squares, with an extra
+// zero row and column so the sum of any area is four lookups.
+// source: https://en.wikipedia.org/wiki/Summed-area_table
+fn get_integral_images(img: &GrayImage) -> (Vec<f64>, Vec<f64>) {
+    let (width, height) = img.dimensions();
+    let stride = (width + 1) as usize;
+    let mut sums = vec![0.0; stride * (height + 1) as usize];
+    let mut squares = sums.clone();
+    for y in 0..height as usize {
+        for x in 0..width as usize {
+            let p = img.get_pixel(x as u32, y as u32).0[0] as f64;
+            let i = (y + 1) * stride + x + 1;
+            sums[i] = p + sums[i - 1] + sums[i - stride] - sums[i - stride - 1];
+            squares[i] = p * p + squares[i - 1] + squares[i - stride] - squares[i - stride - 1];
+        }
+    }
+    (sums, squares)
+}
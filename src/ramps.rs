@@ -0,0 +1,203 @@
+/* Character ramps:
+
+a ramp is a list of characters ordered from the darkest (no ink) to the
+brightest (most ink), the luminance of a chunck picks a character from it
+linearly, so a character can be repeated to give it a wider range.
+
+sources: http://paulbourke.net/dataformats/asciiart/
+         https://en.wikipedia.org/wiki/Box-drawing_character
+*/
+
+// (name, ramp) pairs, the first three are the default tables of the styles
+pub const RAMPS: [(&str, &str); 11] = [
+    ("ascii", "    ...,,,';:<>lobdxkO0KXNWM"),
+    ("numbers", "    017694238"),
+    ("blocks", "    ░▒▓█"),
+    ("standard", " .:-=+*#%@"),
+    (
+        "bourke",
+        " .'`^\",:;Il!i><~+_-?][}{1)(|\\/tfjrxnuvczXYUJCLQ0OZmwqpdbkhao*#MW&8%B@$",
+    ),
+    ("short", " .oO@"),
+    ("letters", " ilrvxzuoacnemwgpqdbkhXUZOQMWB"),
+    ("box", " ·╴─┄┼╋╬▒▓█"),
+    ("dots", " ⠁⠃⠇⡇⣇⣧⣷⣿"),
+    ("shades", " ░▒▓█"),
+    ("cjk", "\u{3000}、一二三十工土王田国回品圖"),
+];
+
+pub fn get_ramp(name: &str) -> Option<Vec<char>> {
+    RAMPS
+        .iter()
+        .find(|(ramp_name, _)| *ramp_name == name)
+        .map(|(_, ramp)| ramp.chars().collect())
+}
+
+pub fn get_names() -> Vec<&'static str> {
+    RAMPS.iter().map(|(name, _)| *name).collect()
+}
+
+// reading a ramp from a file, every character is taken as it is
+// (commas and spaces too), only the line breaks are ignored.
+pub fn read_ramp_file(path: &str) -> Result<Vec<char>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let ramp = text
+        .chars()
+        .filter(|ch| *ch != '\n' && *ch != '\r')
+        .collect::<Vec<char>>();
+    if ramp.is_empty() {
+        return Err(format!("{}: the file is empty", path));
+    }
+    Ok(ramp)
+}
+
+// wide characters (cjk, fullwidth forms) take two columns in the terminal
+// source: https://www.unicode.org/reports/tr11/
+pub fn is_wide(ch: char) -> bool {
+    matches!(ch as u32,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3040..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x20000..=0x3FFFD)
+}
+
+/* Weighted tables:
+
+a table token can give its character a luminance (0-255) or a range of
+luminance, like " =0-60,.=90,:,#=200,@=255":
+- the characters without a value are spread evenly between their neighbours
+- every luminance takes the character with the closest value or range
+- the table is expanded to 256 characters, one for every luminance, so it
+  is indexed like any other table.
+a plain table (no values) is kept as it is.
+*/
+
+pub fn parse_table(arg: &str) -> Vec<char> {
+    let tokens = arg
+        .split(',')
+        .map(parse_token)
+        .collect::<Vec<(char, Option<(f32, f32)>)>>();
+    if tokens.iter().all(|(_, range)| range.is_none()) {
+        return tokens.iter().map(|(ch, _)| *ch).collect();
+    }
+
+    // spreading the characters without a value between the ones around them
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if let Some(range) = tokens[i].1 {
+            ranges.push(range);
+            i += 1;
+            continue;
+        }
+        let start = ranges
+            .last()
+            .map(|range: &(f32, f32)| range.1)
+            .unwrap_or(0.0);
+        let count = tokens[i..].iter().take_while(|(_, r)| r.is_none()).count();
+        let end = tokens
+            .get(i + count)
+            .and_then(|(_, range)| range.map(|range| range.0))
+            .unwrap_or(255.0);
+        for k in 1..=count {
+            let value = start + (end - start) * k as f32 / (count + 1) as f32;
+            ranges.push((value, value));
+        }
+        i += count;
+    }
+
+    (0..=255)
+        .map(|lumi| {
+            let lumi = lumi as f32;
+            let distance = |(low, high): (f32, f32)| (low - lumi).max(lumi - high).max(0.0);
+            let mut best = 0;
+            for (i, range) in ranges.iter().enumerate() {
+                if distance(*range) < distance(ranges[best]) {
+                    best = i;
+                }
+            }
+            tokens[best].0
+        })
+        .collect()
+}
+
+// a token is a character, optionally followed by "=VALUE" or "=LOW-HIGH"
+fn parse_token(token: &str) -> (char, Option<(f32, f32)>) {
+    let token = token.trim();
+    if let Some((ch, value)) = token.rsplit_once('=') {
+        if let Some(range) = parse_range(value) {
+            // the spaces are trimmed, so a value alone is a space
+            return (ch.chars().next().unwrap_or(' '), Some(range));
+        }
+    }
+    (token.chars().next().unwrap_or(' '), None)
+}
+
+fn parse_range(value: &str) -> Option<(f32, f32)> {
+    let (low, high) = match value.split_once('-') {
+        Some((low, high)) => (
+            low.trim().parse::<u8>().ok()?,
+            high.trim().parse::<u8>().ok()?,
+        ),
+        None => {
+            let value = value.trim().parse::<u8>().ok()?;
+            (value, value)
+        }
+    };
+    Some((low.min(high) as f32, low.max(high) as f32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_table_is_kept() {
+        assert_eq!(parse_table("@,#,+,."), vec!['@', '#', '+', '.']);
+        assert_eq!(parse_table(" ,.,:"), vec![' ', '.', ':']);
+    }
+
+    #[test]
+    fn unvalued_characters_are_spread_between_neighbours() {
+        let table = parse_table(" =0,.,:,@=255");
+        assert_eq!(table.len(), 256);
+        assert_eq!(table[0], ' ');
+        assert_eq!(table[42], ' ');
+        assert_eq!(table[43], '.');
+        assert_eq!(table[85], '.');
+        assert_eq!(table[170], ':');
+        assert_eq!(table[255], '@');
+    }
+
+    #[test]
+    fn ranges_cover_the_ends() {
+        let table = parse_table(" =0-60,@=200-255");
+        assert!(table[..=60].iter().all(|ch| *ch == ' '));
+        assert!(table[200..].iter().all(|ch| *ch == '@'));
+        assert_eq!(table[129], ' ');
+        assert_eq!(table[131], '@');
+    }
+
+    #[test]
+    fn unvalued_ends_use_the_full_range() {
+        // the characters before the first value are spread from 0, the ones after the
+        // last value up to 255
+        let table = parse_table(".,#=128,@");
+        assert_eq!(table[0], '.');
+        assert_eq!(table[128], '#');
+        assert_eq!(table[255], '@');
+    }
+
+    #[test]
+    fn equal_sign_is_a_character() {
+        let table = parse_table(" =0,=,@=255");
+        assert_eq!(table[128], '=');
+        let table = parse_table(" =0,==100,@=255");
+        assert_eq!(table[100], '=');
+    }
+}
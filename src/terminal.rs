@@ -0,0 +1,210 @@
+use crate::arguments::config::ColorDepth;
+
+/* Querying the terminal:
+
+- open the terminal (/dev/tty) so it works even if stdout is piped
+- switch it to raw mode so the reply is not echoed and can be read
+  without waiting for a new line
+- write the query followed by a "primary device attributes" (DA1) query,
+  every terminal answers DA1, so when its reply arrives we know there is
+  nothing else coming (no need to wait the timeout if the first query
+  is not supported).
+- restore the terminal mode
+source: https://invisible-island.net/xterm/ctlseqs/ctlseqs.html
+*/
+
+// the longest time to wait for the terminal to answer
+const TIMEOUT_MS: i32 = 200;
+
+// send a query to the terminal and return everything it answered,
+// including the DA1 reply at the end, None if it didn't answer.
+#[cfg(unix)]
+pub fn query(request: &str) -> Option<String> {
+    use std::fs::OpenOptions;
+    use std::io::{Read, Write};
+    use std::os::unix::io::AsRawFd;
+
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    let fd = tty.as_raw_fd();
+
+    let mut original = unsafe { std::mem::zeroed::<libc::termios>() };
+    if unsafe { libc::tcgetattr(fd, &mut original) } != 0 {
+        return None;
+    }
+    let mut raw = original;
+    raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+    raw.c_cc[libc::VMIN] = 0;
+    raw.c_cc[libc::VTIME] = 0;
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } != 0 {
+        return None;
+    }
+
+    let mut reply = Vec::new();
+    if write!(tty, "{}\x1B[c", request).is_ok() && tty.flush().is_ok() {
+        let mut buffer = [0u8; 256];
+        let mut poll_fd = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // reading until the DA1 reply (ESC [ ? ... c) or the timeout
+        while !is_da1_done(&reply) {
+            if unsafe { libc::poll(&mut poll_fd, 1, TIMEOUT_MS) } <= 0 {
+                break;
+            }
+            match tty.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(count) => reply.extend_from_slice(&buffer[..count]),
+            }
+        }
+    }
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &original) };
+
+    if reply.is_empty() {
+        None
+    } else {
+        Some(String::from_utf8_lossy(&reply).to_string())
+    }
+}
+
+#[cfg(not(unix))]
+pub fn query(_request: &str) -> Option<String> {
+    None
+}
+
+fn is_da1_done(reply: &[u8]) -> bool {
+    match reply.windows(3).rposition(|w| w == b"\x1B[?") {
+        Some(start) => reply[start..].contains(&b'c'),
+        None => false,
+    }
+}
+
+// the background color of the terminal, replied to OSC 11 as
+// "ESC ] 11 ; rgb:RRRR/GGGG/BBBB" with 1 to 4 hex digits for every channel.
+pub fn get_background_color() -> Option<[u8; 3]> {
+    let reply = query("\x1B]11;?\x1B\\")?;
+    let start = reply.find("rgb:")? + 4;
+    let mut color = [0u8; 3];
+    let mut channels = reply[start..].split('/');
+    for channel in color.iter_mut() {
+        let digits = channels
+            .next()?
+            .chars()
+            .take_while(|ch| ch.is_ascii_hexdigit())
+            .collect::<String>();
+        if digits.is_empty() || digits.len() > 4 {
+            return None;
+        }
+        let value = u32::from_str_radix(&digits, 16).ok()?;
+        let max = (1u32 << (4 * digits.len())) - 1;
+        *channel = (value * 255 / max) as u8;
+    }
+    Some(color)
+}
+
+/* Detecting what the terminal supports (--output auto):
+
+- the environment first: COLORTERM, TERM, TERM_PROGRAM and the variables
+  some terminals set (KITTY_WINDOW_ID, ..), they cost nothing
+- then if stdout is a terminal, querying it:
+  - kitty graphics: a tiny query image (a=q), kitty/wezterm/ghostty reply "OK"
+  - sixel: the DA1 reply lists the attribute 4 when sixel is supported
+*/
+
+// the terminal programs that support the kitty graphics protocol
+const KITTY_PROGRAMS: [&str; 2] = ["WezTerm", "ghostty"];
+// the terminal programs that support the iterm2 inline images
+const ITERM_PROGRAMS: [&str; 3] = ["iTerm.app", "WezTerm", "mintty"];
+// the terminal programs that support true colors without setting COLORTERM
+const TRUECOLOR_PROGRAMS: [&str; 5] = ["iTerm.app", "WezTerm", "ghostty", "vscode", "Hyper"];
+
+// the protocols for showing the real image
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Graphics {
+    Kitty,
+    Iterm,
+    Sixel,
+}
+
+#[cfg(unix)]
+pub fn is_tty() -> bool {
+    unsafe { libc::isatty(libc::STDOUT_FILENO) == 1 }
+}
+
+#[cfg(not(unix))]
+pub fn is_tty() -> bool {
+    false
+}
+
+fn get_env(name: &str) -> String {
+    std::env::var(name).unwrap_or_default()
+}
+
+// the best graphics protocol of the terminal, None if it supports none of them
+pub fn detect_graphics() -> Option<Graphics> {
+    let term = get_env("TERM");
+    let program = get_env("TERM_PROGRAM");
+    if term == "xterm-kitty"
+        || std::env::var_os("KITTY_WINDOW_ID").is_some()
+        || KITTY_PROGRAMS.contains(&program.as_str())
+    {
+        return Some(Graphics::Kitty);
+    }
+    if ITERM_PROGRAMS.contains(&program.as_str()) {
+        return Some(Graphics::Iterm);
+    }
+    if !is_tty() {
+        return None;
+    }
+    // one query for both, the kitty reply (if any) comes before the DA1 reply
+    let kitty_query =
+        crate::operations::graphics::passthrough("\x1B_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1B\\");
+    let reply = query(&kitty_query)?;
+    if reply.contains("\x1B_Gi=31;OK") {
+        return Some(Graphics::Kitty);
+    }
+    if has_da1_attribute(&reply, 4) {
+        return Some(Graphics::Sixel);
+    }
+    None
+}
+
+// the DA1 reply is "ESC [ ? ATTRIBUTE ; ATTRIBUTE ; .. c"
+fn has_da1_attribute(reply: &str, attribute: u32) -> bool {
+    let start = match reply.rfind("\x1B[?") {
+        Some(start) => start + 3,
+        None => return false,
+    };
+    reply[start..]
+        .split([';', 'c'])
+        .any(|value| value.parse::<u32>() == Ok(attribute))
+}
+
+// the color depth of the terminal from the environment
+pub fn detect_colors() -> ColorDepth {
+    let term = get_env("TERM");
+    let colorterm = get_env("COLORTERM");
+    let program = get_env("TERM_PROGRAM");
+    if std::env::var_os("NO_COLOR").is_some() || term == "dumb" {
+        ColorDepth::None
+    } else if colorterm == "truecolor"
+        || colorterm == "24bit"
+        || term.ends_with("direct")
+        || term == "xterm-kitty"
+        || TRUECOLOR_PROGRAMS.contains(&program.as_str())
+    {
+        ColorDepth::TrueColor
+    } else if term.contains("256color") {
+        ColorDepth::Ansi256
+    } else if term == "linux" || term.starts_with("vt") {
+        ColorDepth::Ansi8
+    } else if term.is_empty() {
+        ColorDepth::None
+    } else {
+        ColorDepth::Ansi16
+    }
+}
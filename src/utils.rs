@@ -1,79 +1,342 @@
-use crate::arguments::config::Config;
-use image::{DynamicImage, GenericImageView, GrayImage, RgbaImage};
+use crate::arguments::config::{ColorDepth, ColorSample, Config};
+use crate::color::{get_closest_index, srgb_to_oklab};
+use crate::luminance::get_gray;
+use crate::operations::filters::apply_filters;
+use crate::operations::palette::{get_closest_color, get_palette_oklab, quantize};
+use image::{DynamicImage, GenericImageView, RgbaImage};
+use std::{fs::File, io::BufReader, sync::OnceLock};
 
-// luminance formula credits: https://stackoverflow.com/a/596243
-// >>> Luminance = 0.2126*R + 0.7152*G + 0.0722*B <<<
-// calculate RGB values to get luminance of the pixel
-pub fn get_luminance(r: u8, g: u8, b: u8) -> f32 {
-    let r = 0.2126 * (r as f32);
-    let g = 0.7152 * (g as f32);
-    let b = 0.0722 * (b as f32);
-    r + g + b
+// pixels with alpha below this are treated as fully transparent
+const ALPHA_THRESHOLD: u8 = 128;
+
+pub fn is_transparent(pixel: [u8; 4]) -> bool {
+    pixel[3] < ALPHA_THRESHOLD
 }
 
-// colorize a character by surrounding it with true  term colors
-pub fn colorize(rgb: &[u8; 3], ch: char, bg_fg: u8) -> String {
-    let prefix = format!("\x1B[{};2;{};{};{}m", bg_fg, rgb[0], rgb[1], rgb[2]);
-    let postfix = "\x1B[0m";
-    format!("{}{}{}", prefix, ch, postfix)
+// blend every pixel over a solid background color and make it opaque,
+// used when the user gives --bg-color so transparent regions get a color.
+pub fn composite_background(img: &mut RgbaImage, bg: [u8; 3]) {
+    for pixel in img.pixels_mut() {
+        let alpha = pixel.0[3] as f32 / 255.0;
+        for (channel, bg_channel) in pixel.0.iter_mut().zip(bg.iter()) {
+            *channel = (*channel as f32 * alpha + *bg_channel as f32 * (1.0 - alpha)).round() as u8;
+        }
+        pixel.0[3] = 255;
+    }
 }
 
-//rescale the image and convert to image buffer
-pub fn open_and_resize(config: &Config) -> Option<RgbaImage> {
+// convert the pixels to gray in place, unlike DynamicImage::grayscale
+// this keeps the alpha channel. the gray keeps the luminance of the chosen
+// formula, so converting twice changes nothing.
+pub fn grayscale(img: &mut RgbaImage, config: &Config) {
+    for pixel in img.pixels_mut() {
+        let [r, g, b, _] = pixel.0;
+        let lumi = get_gray(config.luma, r, g, b).round() as u8;
+        pixel.0[..3].clone_from_slice(&[lumi, lumi, lumi]);
+    }
+}
+
+// the colors of the opaque pixels in a block of pixels starting from x,y
+pub fn get_block_colors(img: &RgbaImage, x: u32, y: u32, width: u32, height: u32) -> Vec<[u8; 3]> {
+    let mut out = Vec::new();
+    for iy in y..y + height {
+        for ix in x..x + width {
+            let [r, g, b, a] = img.get_pixel(ix, iy).0;
+            if !is_transparent([r, g, b, a]) {
+                out.push([r, g, b]);
+            }
+        }
+    }
+    out
+}
+
+// reduce a list of colors to the one color representing them,
+// None if the list is empty (all the pixels were transparent).
+pub fn reduce_colors(colors: &[[u8; 3]], sample: ColorSample) -> Option<[u8; 3]> {
+    if colors.is_empty() {
+        return None;
+    }
+    let average = |colors: &[[u8; 3]]| {
+        let mut sum = [0u32; 3];
+        for color in colors {
+            for (total, channel) in sum.iter_mut().zip(color.iter()) {
+                *total += *channel as u32;
+            }
+        }
+        let count = colors.len() as u32;
+        [
+            (sum[0] / count) as u8,
+            (sum[1] / count) as u8,
+            (sum[2] / count) as u8,
+        ]
+    };
+    let color = match sample {
+        ColorSample::Average => average(colors),
+        // the median of every channel on its own
+        ColorSample::Median => {
+            let mut out = [0u8; 3];
+            for (i, channel) in out.iter_mut().enumerate() {
+                let mut values = colors.iter().map(|c| c[i]).collect::<Vec<u8>>();
+                values.sort_unstable();
+                *channel = values[values.len() / 2];
+            }
+            out
+        }
+        // grouping the colors by their top 3 bits of every channel, and
+        // averaging the group with the most colors
+        ColorSample::Dominant => {
+            let bucket = |c: &[u8; 3]| (c[0] >> 5, c[1] >> 5, c[2] >> 5);
+            let mut counts = std::collections::HashMap::new();
+            for color in colors {
+                *counts.entry(bucket(color)).or_insert(0) += 1;
+            }
+            let dominant = counts
+                .into_iter()
+                .max_by_key(|(key, count)| (*count, *key))
+                .map(|(key, _)| key)
+                .unwrap();
+            let group = colors
+                .iter()
+                .filter(|c| bucket(c) == dominant)
+                .copied()
+                .collect::<Vec<[u8; 3]>>();
+            average(&group)
+        }
+    };
+    Some(color)
+}
+
+// reduce_colors with the sampling the user picked, with a palette the color
+// is moved to the closest palette color, the average (or the median) of a
+// few palette colors is usually not one of them.
+pub fn reduce_cell_colors(colors: &[[u8; 3]], config: &Config) -> Option<[u8; 3]> {
+    let color = reduce_colors(colors, config.cell_color)?;
+    Some(match &config.palette {
+        Some(palette) => get_closest_color(&color, palette, &get_palette_oklab(palette)),
+        None => color,
+    })
+}
+
+// get the color of a cell (a block of pixels starting from x,y) using the
+// sampling the user picked, transparent pixels are not counted.
+pub fn get_cell_color(
+    img: &RgbaImage,
+    config: &Config,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> [u8; 3] {
+    let colors = get_block_colors(img, x, y, width, height);
+    reduce_cell_colors(&colors, config).unwrap_or_else(|| {
+        let [r, g, b, _] = img.get_pixel(x, y).0;
+        [r, g, b]
+    })
+}
+
+// the first 16 colors of xterm, the terminal themes change them
+// but these are the usual values.
+// source: https://en.wikipedia.org/wiki/ANSI_escape_code#Colors
+const ANSI_COLORS: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [205, 0, 0],
+    [0, 205, 0],
+    [205, 205, 0],
+    [0, 0, 238],
+    [205, 0, 205],
+    [0, 205, 205],
+    [229, 229, 229],
+    [127, 127, 127],
+    [255, 0, 0],
+    [0, 255, 0],
+    [255, 255, 0],
+    [92, 92, 255],
+    [255, 0, 255],
+    [0, 255, 255],
+    [255, 255, 255],
+];
+
+// the levels of every channel in the 6*6*6 color cube of the 256 colors
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+// the color of a xterm 256 color (16 - 255)
+fn get_xterm_color(index: u8) -> [u8; 3] {
+    if index >= 232 {
+        let gray = 8 + (index - 232) * 10;
+        [gray, gray, gray]
+    } else {
+        let i = (index - 16) as usize;
+        [
+            CUBE_LEVELS[i / 36],
+            CUBE_LEVELS[i / 6 % 6],
+            CUBE_LEVELS[i % 6],
+        ]
+    }
+}
+
+// the closest of the 256 colors (in Oklab), the first 16 are skipped
+// because every terminal theme changes them.
+pub fn get_closest_xterm(rgb: &[u8; 3]) -> u8 {
+    static COLORS: OnceLock<Vec<[f32; 3]>> = OnceLock::new();
+    let colors = COLORS.get_or_init(|| {
+        (16..=255)
+            .map(|index| srgb_to_oklab(&get_xterm_color(index)))
+            .collect()
+    });
+    16 + get_closest_index(&srgb_to_oklab(rgb), colors) as u8
+}
+
+// the closest of the first count (8 or 16) ansi colors (in Oklab)
+pub fn get_closest_ansi(rgb: &[u8; 3], count: usize) -> u8 {
+    static COLORS: OnceLock<Vec<[f32; 3]>> = OnceLock::new();
+    let colors = COLORS.get_or_init(|| ANSI_COLORS.iter().map(srgb_to_oklab).collect());
+    get_closest_index(&srgb_to_oklab(rgb), &colors[..count]) as u8
+}
+
+// the SGR parameters of a color for the foreground (38) or the background (48)
+fn get_color_code(rgb: &[u8; 3], bg_fg: u8, depth: ColorDepth) -> Option<String> {
+    let code = match depth {
+        ColorDepth::TrueColor => format!("{};2;{};{};{}", bg_fg, rgb[0], rgb[1], rgb[2]),
+        ColorDepth::Ansi256 => format!("{};5;{}", bg_fg, get_closest_xterm(rgb)),
+        ColorDepth::Ansi16 | ColorDepth::Ansi8 => {
+            let count = if depth == ColorDepth::Ansi8 { 8 } else { 16 };
+            let index = get_closest_ansi(rgb, count);
+            // 30-37 and 90-97 for the foreground, 40-47 and 100-107 for the background
+            let base = if bg_fg == 48 { 40 } else { 30 };
+            match index {
+                0..=7 => format!("{}", base + index),
+                _ => format!("{}", base + 60 + index - 8),
+            }
+        }
+        ColorDepth::None => return None,
+    };
+    Some(code)
+}
+
+// colorize a character by surrounding it with the terminal colors
+pub fn colorize(rgb: &[u8; 3], ch: char, bg_fg: u8, depth: ColorDepth) -> String {
+    match get_color_code(rgb, bg_fg, depth) {
+        Some(code) => format!("\x1B[{}m{}\x1B[0m", code, ch),
+        None => ch.to_string(),
+    }
+}
+
+// colorize a character with a foreground and a background color at once
+pub fn colorize_fg_bg(fg: &[u8; 3], bg: &[u8; 3], ch: char, depth: ColorDepth) -> String {
+    match (get_color_code(fg, 38, depth), get_color_code(bg, 48, depth)) {
+        (Some(fg), Some(bg)) => format!("\x1B[{};{}m{}\x1B[0m", fg, bg, ch),
+        _ => ch.to_string(),
+    }
+}
+
+// open the image path and rotate/flip it based on the exif orientation tag,
+// phones usually save the photo sideways and only set this tag.
+pub fn open_image(config: &Config) -> Option<DynamicImage> {
     let img = if let Ok(image) = image::open(&config.image_file) {
         image
     } else {
         eprintln!("Image path is not correct, OR image format is not supported!\n try -h | --help");
         return None;
     };
-    let width = match config.original_size {
-        true => img.width(),
-        false => ((img.width() / config.scale) / 2) as u32,
+    if !config.exif {
+        return Some(img);
+    }
+    // orientation values 2-8 are the flips/rotations needed to show the image upright
+    let img = match get_exif_orientation(&config.image_file) {
+        2 => img.fliph(),
+        3 => img.rotate180(),
+        4 => img.flipv(),
+        5 => img.rotate90().fliph(),
+        6 => img.rotate90(),
+        7 => img.rotate270().fliph(),
+        8 => img.rotate270(),
+        _ => img,
     };
-    let height = match config.original_size {
-        true => img.height(),
-        false => ((img.height() / config.scale) / 4) as u32,
+    Some(img)
+}
+
+// read the orientation tag from the image metadata,
+// 1 (the default orientation) if the image has no exif data.
+fn get_exif_orientation(path: &str) -> u32 {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return 1,
     };
-    let img = img.resize_exact(width, height, image::imageops::FilterType::Lanczos3);
-    let img = if config.colored {
-        img.into_rgba8()
-    } else {
-        img.grayscale().into_rgba8()
+    let exif = match exif::Reader::new().read_from_container(&mut BufReader::new(file)) {
+        Ok(exif) => exif,
+        Err(_) => return 1,
     };
-    Some(img)
+    exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+        .and_then(|field| field.value.get_uint(0))
+        .unwrap_or(1)
 }
 
-pub fn resize(img: DynamicImage, config: &Config) -> RgbaImage {
+//rescale the image and convert to image buffer
+pub fn open_and_resize(config: &Config) -> Option<RgbaImage> {
+    open_and_resize_by(config, (1.0, 1.0))
+}
+
+// same as open_and_resize, but the size is multiplied by the factor (x, y),
+// for the styles that sample more pixels for each character.
+pub fn open_and_resize_by(config: &Config, factor: (f32, f32)) -> Option<RgbaImage> {
+    let img = open_image(config)?;
+    Some(resize_exact_by(img, config, factor))
+}
+
+// the size to resize the image to, at least 1x1 so tiny images don't end up
+// empty (the encoders and the styles can't handle an empty image).
+fn get_resized_size(img: &DynamicImage, config: &Config, factor: (f32, f32)) -> (u32, u32) {
     let (width, height) = match config.original_size {
         false => {
-            let width = ((img.width() / config.scale) / 2) as u32;
-            let height = ((img.height() / config.scale) / 4) as u32;
+            let width = (img.width() / config.scale) / 2;
+            let height = (img.height() / config.scale) / 4;
             (width, height)
         }
         true => (img.width(), img.height()),
     };
-    img.resize(width, height, image::imageops::FilterType::Lanczos3)
-        .to_rgba8()
+    (
+        ((width as f32 * factor.0).round() as u32).max(1),
+        ((height as f32 * factor.1).round() as u32).max(1),
+    )
 }
 
-// this will open the image path,
-// and resize the image and turn it into image buffer;
-pub fn get_luma_buffer(config: &Config) -> Option<GrayImage> {
-    let img = if let Ok(image) = image::open(&config.image_file) {
-        image
-    } else {
-        eprintln!("Image path is not correct, OR image format is not supported!\n try -h | --help");
-        return None;
-    };
-    let width = match config.original_size {
-        true => img.width(),
-        false => ((img.width() / config.scale) / 2) as u32,
-    };
-    let height = match config.original_size {
-        true => img.height(),
-        false => ((img.height() / config.scale) / 4) as u32,
-    };
-    let img = img.resize_exact(width, height, image::imageops::FilterType::Lanczos3);
-    let img = img.to_luma8();
-    Some(img)
+// the image is resized to exactly (width * height) times the factor, and
+// prepared for the styles (background, filters, palette and grayscale),
+// used for the static images and the gif frames of open_and_resize_by's styles.
+pub fn resize_exact_by(img: DynamicImage, config: &Config, factor: (f32, f32)) -> RgbaImage {
+    let (width, height) = get_resized_size(&img, config, factor);
+    let mut img = img
+        .resize_exact(width, height, image::imageops::FilterType::Lanczos3)
+        .into_rgba8();
+    if let Some(bg) = config.bg_color {
+        composite_background(&mut img, bg);
+    }
+    apply_filters(&mut img, config);
+    // with --dither the palette is used by the dithering instead
+    if let (Some(palette), false) = (&config.palette, config.dither) {
+        quantize(&mut img, palette);
+    }
+    if !config.colored {
+        grayscale(&mut img, config);
+    }
+    img
+}
+
+pub fn resize(img: DynamicImage, config: &Config) -> RgbaImage {
+    resize_by(img, config, (1.0, 1.0))
+}
+
+pub fn resize_by(img: DynamicImage, config: &Config, factor: (f32, f32)) -> RgbaImage {
+    let (width, height) = get_resized_size(&img, config, factor);
+    let mut img = img
+        .resize(width, height, image::imageops::FilterType::Lanczos3)
+        .to_rgba8();
+    if let Some(bg) = config.bg_color {
+        composite_background(&mut img, bg);
+    }
+    apply_filters(&mut img, config);
+    if let (Some(palette), false) = (&config.palette, config.dither) {
+        quantize(&mut img, palette);
+    }
+    img
 }